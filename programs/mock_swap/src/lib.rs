@@ -72,6 +72,50 @@ pub mod mock_swap {
 
         Ok(())
     }
+
+    /// Mock two-mint swap for Localnet verification.
+    /// Moves `amount_in` from `input` into the pool and `amount_out` from the
+    /// pool into `output`, at whatever rate the test chooses.
+    ///
+    /// NOTE: This is for LOCALNET TESTING ONLY.
+    ///
+    /// The pool accounts belong to `pool_authority`, which signs the outer
+    /// transaction. This lets tests sell a non-base position for Base Asset.
+    pub fn swap_pair(ctx: Context<SwapPair>, amount_in: u64, amount_out: u64) -> Result<()> {
+        let authority_key = ctx.accounts.authority.key();
+
+        // 1. Ownership Checks
+        require!(
+            ctx.accounts.input.owner == authority_key,
+            MockSwapError::InvalidInputOwner
+        );
+        require!(
+            ctx.accounts.output.owner == authority_key,
+            MockSwapError::InvalidOutputOwner
+        );
+
+        // 2. Input -> Pool
+        let cpi_accounts = Transfer {
+            from: ctx.accounts.input.to_account_info(),
+            to: ctx.accounts.pool_input.to_account_info(),
+            authority: ctx.accounts.authority.to_account_info(),
+        };
+        let cpi_ctx = CpiContext::new(ctx.accounts.token_program.to_account_info(), cpi_accounts);
+        token::transfer(cpi_ctx, amount_in)?;
+
+        // 3. Pool -> Output
+        let cpi_accounts = Transfer {
+            from: ctx.accounts.pool_output.to_account_info(),
+            to: ctx.accounts.output.to_account_info(),
+            authority: ctx.accounts.pool_authority.to_account_info(),
+        };
+        let cpi_ctx = CpiContext::new(ctx.accounts.token_program.to_account_info(), cpi_accounts);
+        token::transfer(cpi_ctx, amount_out)?;
+
+        msg!("MockSwap pair: amount_in={}, amount_out={}", amount_in, amount_out);
+
+        Ok(())
+    }
 }
 
 #[derive(Accounts)]
@@ -92,6 +136,35 @@ pub struct Swap<'info> {
     pub token_program: Program<'info, Token>,
 }
 
+#[derive(Accounts)]
+pub struct SwapPair<'info> {
+    /// The authority (TraderState PDA).
+    /// CHECK: Authority passed via CPI from stellalpha_vault.
+    pub authority: AccountInfo<'info>,
+
+    /// Input token account. Must be owned by authority.
+    #[account(mut)]
+    pub input: Account<'info, TokenAccount>,
+
+    /// Output token account. Must be owned by authority.
+    #[account(mut)]
+    pub output: Account<'info, TokenAccount>,
+
+    /// Owner of both pool accounts.
+    pub pool_authority: Signer<'info>,
+
+    /// Receives the input tokens.
+    #[account(mut)]
+    pub pool_input: Account<'info, TokenAccount>,
+
+    /// Pays out the output tokens.
+    #[account(mut)]
+    pub pool_output: Account<'info, TokenAccount>,
+
+    /// SPL Token Program.
+    pub token_program: Program<'info, Token>,
+}
+
 #[error_code]
 pub enum MockSwapError {
     #[msg("Input token account not owned by authority.")]
@@ -1,7 +1,42 @@
 use anchor_lang::prelude::*;
 
-// Jupiter V6 Program ID (Mocked to Memo v1 Program for Devnet)
-pub const JUPITER_PROGRAM_ID: Pubkey = pubkey!("Memo1UhkJRfHyvLMcVucJwxXeuD728EqVDDwQDxFMNo");
+// Jupiter V6 Program ID. Seeded into GlobalConfig.approved_swap_programs on init.
+pub const JUPITER_PROGRAM_ID: Pubkey = pubkey!("JUP6LkbZbjS1jKKwapdHNy74zcZ3tLUZoi5QNyVTaV4");
+
+// Maximum number of approved swap programs in GlobalConfig
+pub const MAX_SWAP_PROGRAMS: usize = 8;
 
 // Platform Fee Wallet (Replace with actual address in production)
-pub const PLATFORM_FEE_WALLET: Pubkey = pubkey!("11111111111111111111111111111111"); 
+pub const PLATFORM_FEE_WALLET: Pubkey = pubkey!("11111111111111111111111111111111");
+
+// Minimum time between performance fee crystallizations on a TraderState (7 days).
+// Test builds with short-crystallization-interval wait 5 seconds instead.
+// Listed in the IDL so tests can tell which build they run against.
+#[constant]
+#[cfg(not(feature = "short-crystallization-interval"))]
+pub const MIN_CRYSTALLIZATION_INTERVAL: i64 = 7 * 24 * 60 * 60;
+#[constant]
+#[cfg(feature = "short-crystallization-interval")]
+pub const MIN_CRYSTALLIZATION_INTERVAL: i64 = 5;
+
+// Hard caps on admin-configurable fees (basis points)
+pub const MAX_PLATFORM_FEE_BPS: u16 = 100; // 1%
+pub const MAX_PERFORMANCE_FEE_BPS: u16 = 3000; // 30%
+
+// Notice period between proposing and applying a fee change (3 days)
+pub const FEE_CHANGE_TIMELOCK: i64 = 3 * 24 * 60 * 60;
+
+// Maximum number of protocol-wide default allowed mints in GlobalConfig
+pub const MAX_DEFAULT_MINTS: usize = 16;
+
+// Maximum number of mints in a UserVault whitelist (account grows via realloc)
+pub const MAX_ALLOWED_MINTS: usize = 64;
+
+// Time the backend has to complete a portfolio sync before the owner may abort it (1 day)
+pub const SYNC_DURATION: i64 = 24 * 60 * 60;
+
+// Length of the fixed TraderState swap rate-limit window (1 day)
+pub const RATE_LIMIT_WINDOW: i64 = 24 * 60 * 60;
+
+// Number of swaps kept in a TraderState's TradeJournal ring buffer
+pub const TRADE_JOURNAL_CAPACITY: usize = 64;
@@ -41,4 +41,70 @@ pub enum ErrorCode {
     TraderNotInitialized,
     #[msg("TraderState already initialized.")]
     AlreadyInitialized,
+    #[msg("Arithmetic overflow.")]
+    MathOverflow,
+    #[msg("TraderState holds non-base positions. Unwind to Base Asset first.")]
+    PositionsOpen,
+    #[msg("Minimum interval between performance fee crystallizations has not elapsed.")]
+    CrystallizationTooSoon,
+    #[msg("Fee exceeds the protocol maximum.")]
+    FeeTooHigh,
+    #[msg("No pending fee change to apply.")]
+    NoPendingFeeChange,
+    #[msg("Fee change timelock has not expired.")]
+    TimelockNotExpired,
+    #[msg("Swap program is not on the approved list.")]
+    SwapProgramNotApproved,
+    #[msg("Approved swap program list is full.")]
+    SwapProgramListFull,
+    #[msg("Default allowed mint list is full.")]
+    DefaultMintListFull,
+    #[msg("Vault allowed mint list has reached its maximum size.")]
+    WhitelistFull,
+    #[msg("Protocol trading is halted.")]
+    TradingHalted,
+    #[msg("TraderState is already settled.")]
+    AlreadySettled,
+    #[msg("Account already uses the current account layout.")]
+    AlreadyMigrated,
+    #[msg("Risk limit must be between 0 and 10000 bps.")]
+    InvalidRiskLimit,
+    #[msg("Trade size exceeds the TraderState maximum.")]
+    TradeSizeExceeded,
+    #[msg("min_amount_out allows more slippage than the TraderState maximum.")]
+    SlippageLimitExceeded,
+    #[msg("TraderState swap count limit reached for the current window.")]
+    SwapCountLimitExceeded,
+    #[msg("TraderState swap volume limit reached for the current window.")]
+    SwapVolumeLimitExceeded,
+    #[msg("TraderPosition account required for a non-base swap side.")]
+    PositionAccountMissing,
+    #[msg("Settlement must include every TraderPosition with its ATA.")]
+    SettlementAccountsMismatch,
+    #[msg("A non-base TraderState ATA still holds tokens.")]
+    StrandedAssets,
+    #[msg("TraderState is already in sync phase.")]
+    AlreadySyncing,
+    #[msg("TraderState is not in sync phase.")]
+    NotSyncing,
+    #[msg("Portfolio sync deadline has passed.")]
+    SyncExpired,
+    #[msg("Portfolio sync deadline has not passed yet.")]
+    SyncDeadlineNotReached,
+    #[msg("TraderState is settling. Only swaps into the Base Asset are allowed.")]
+    TraderSettling,
+    #[msg("TraderState is closed.")]
+    TraderClosed,
+    #[msg("Illegal TraderState status transition.")]
+    InvalidStatusTransition,
+    #[msg("The Base Asset is tracked by current_value and cannot have a TraderPosition.")]
+    BaseMintPosition,
+    #[msg("Close every non-base TraderState ATA before closing the TraderState.")]
+    PositionAccountsRemain,
+    #[msg("A slippage limit requires a non-zero expected_amount_out of at least min_amount_out.")]
+    InvalidQuote,
+    #[msg("Migration accounts must be [TraderPosition, ATA] pairs for the TraderState's non-base ATAs.")]
+    MigrationAccountsMismatch,
+    #[msg("Swap token accounts must be the TraderState's ATAs.")]
+    NotTraderAta,
 }
@@ -1,22 +1,103 @@
 use anchor_lang::prelude::*;
+use anchor_lang::system_program;
 use crate::state::*;
 use crate::errors::ErrorCode;
+use crate::constants::{
+    MAX_PLATFORM_FEE_BPS, MAX_PERFORMANCE_FEE_BPS, FEE_CHANGE_TIMELOCK,
+    JUPITER_PROGRAM_ID, MAX_SWAP_PROGRAMS, MAX_DEFAULT_MINTS, MAX_ALLOWED_MINTS,
+};
 
-pub fn initialize_global_config(ctx: Context<InitializeGlobalConfig>) -> Result<()> {
+/// Create the GlobalConfig. The signer becomes admin; `guardian` may halt trading.
+pub fn initialize_global_config(ctx: Context<InitializeGlobalConfig>, guardian: Pubkey) -> Result<()> {
     let config = &mut ctx.accounts.global_config;
     config.admin = ctx.accounts.admin.key();
     config.platform_fee_bps = 10; // 0.1% default
     config.performance_fee_bps = 2000; // 20% default
     config.legacy_trading_enabled = false; // Disabled by default for new deployments
-    msg!("Global Config initialized. Admin: {}. Legacy trading disabled.", config.admin);
+    config.pending_platform_fee_bps = 0;
+    config.pending_performance_fee_bps = 0;
+    config.fee_change_effective_at = 0;
+    config.pending_admin = None;
+    config.approved_swap_programs = vec![JUPITER_PROGRAM_ID];
+    config.default_allowed_mints = Vec::new();
+    config.trading_halted = false;
+    config.guardian = guardian;
+    msg!("Global Config initialized. Admin: {}. Guardian: {}. Legacy trading disabled.", config.admin, config.guardian);
     Ok(())
 }
 
-pub fn add_allowed_mint(ctx: Context<ManageWhitelist>, mint: Pubkey) -> Result<()> {
+/// Migrate the GlobalConfig from the original account layout. Admin only.
+/// Fees, admin and the legacy flag are kept; new fields get the same defaults
+/// as initialize_global_config. Admin pays the extra rent.
+pub fn migrate_global_config(ctx: Context<MigrateGlobalConfig>) -> Result<()> {
+    let info = ctx.accounts.global_config.to_account_info();
+    require!(info.data_len() == GlobalConfig::LEGACY_SPACE, ErrorCode::AlreadyMigrated);
+
+    // Legacy layout: discriminator | admin | platform_fee_bps | performance_fee_bps | legacy_trading_enabled
+    let migrated = {
+        let data = info.try_borrow_data()?;
+        require!(
+            data[..8] == *GlobalConfig::DISCRIMINATOR,
+            anchor_lang::error::ErrorCode::AccountDiscriminatorMismatch
+        );
+        let admin = Pubkey::try_from(&data[8..40]).unwrap();
+        require_keys_eq!(admin, ctx.accounts.admin.key(), ErrorCode::Unauthorized);
+
+        GlobalConfig {
+            admin,
+            platform_fee_bps: u16::from_le_bytes([data[40], data[41]]),
+            performance_fee_bps: u16::from_le_bytes([data[42], data[43]]),
+            legacy_trading_enabled: data[44] != 0,
+            pending_platform_fee_bps: 0,
+            pending_performance_fee_bps: 0,
+            fee_change_effective_at: 0,
+            pending_admin: None,
+            approved_swap_programs: vec![JUPITER_PROGRAM_ID],
+            default_allowed_mints: Vec::new(),
+            trading_halted: false,
+            guardian: admin,
+        }
+    };
+
+    let rent_due = Rent::get()?
+        .minimum_balance(GlobalConfig::SPACE)
+        .saturating_sub(info.lamports());
+    if rent_due > 0 {
+        let cpi_accounts = system_program::Transfer {
+            from: ctx.accounts.admin.to_account_info(),
+            to: info.clone(),
+        };
+        let cpi_ctx = CpiContext::new(ctx.accounts.system_program.to_account_info(), cpi_accounts);
+        system_program::transfer(cpi_ctx, rent_due)?;
+    }
+    info.resize(GlobalConfig::SPACE)?;
+    migrated.try_serialize(&mut &mut info.try_borrow_mut_data()?[..])?;
+
+    msg!("Migrated Global Config to current layout. Admin: {}", migrated.admin);
+
+    emit!(GlobalConfigMigrated {
+        admin: migrated.admin,
+        platform_fee_bps: migrated.platform_fee_bps,
+        performance_fee_bps: migrated.performance_fee_bps,
+    });
+
+    Ok(())
+}
+
+/// Add a mint to the vault whitelist. Owner only.
+/// The vault account grows via realloc; the owner pays the extra rent.
+pub fn add_allowed_mint(ctx: Context<AddAllowedMint>, mint: Pubkey) -> Result<()> {
     let vault = &mut ctx.accounts.vault;
     if !vault.allowed_mints.contains(&mint) {
+        require!(vault.allowed_mints.len() < MAX_ALLOWED_MINTS, ErrorCode::WhitelistFull);
         vault.allowed_mints.push(mint);
         msg!("Added allowed mint: {}", mint);
+
+        emit!(AllowedMintUpdated {
+            vault: vault.key(),
+            mint,
+            allowed: true,
+        });
     }
     Ok(())
 }
@@ -26,6 +107,12 @@ pub fn remove_allowed_mint(ctx: Context<ManageWhitelist>, mint: Pubkey) -> Resul
     if let Some(pos) = vault.allowed_mints.iter().position(|x| *x == mint) {
         vault.allowed_mints.remove(pos);
         msg!("Removed allowed mint: {}", mint);
+
+        emit!(AllowedMintUpdated {
+            vault: vault.key(),
+            mint,
+            allowed: false,
+        });
     }
     Ok(())
 }
@@ -44,6 +131,227 @@ pub fn toggle_legacy_trading(ctx: Context<AdminGlobalConfig>) -> Result<()> {
     Ok(())
 }
 
+/// Propose new platform and performance fee rates. Admin only.
+/// Rates are capped by MAX_PLATFORM_FEE_BPS / MAX_PERFORMANCE_FEE_BPS.
+/// Takes effect via apply_fee_change after FEE_CHANGE_TIMELOCK. Re-proposing restarts the timelock.
+pub fn propose_fee_change(ctx: Context<AdminGlobalConfig>, platform_fee_bps: u16, performance_fee_bps: u16) -> Result<()> {
+    require!(platform_fee_bps <= MAX_PLATFORM_FEE_BPS, ErrorCode::FeeTooHigh);
+    require!(performance_fee_bps <= MAX_PERFORMANCE_FEE_BPS, ErrorCode::FeeTooHigh);
+
+    let config = &mut ctx.accounts.global_config;
+    let effective_at = Clock::get()?
+        .unix_timestamp
+        .checked_add(FEE_CHANGE_TIMELOCK)
+        .ok_or(ErrorCode::MathOverflow)?;
+    config.pending_platform_fee_bps = platform_fee_bps;
+    config.pending_performance_fee_bps = performance_fee_bps;
+    config.fee_change_effective_at = effective_at;
+    msg!(
+        "Fee change proposed. Platform: {} bps, Performance: {} bps, Effective at: {}",
+        platform_fee_bps, performance_fee_bps, effective_at
+    );
+
+    emit!(FeeChangeProposed {
+        platform_fee_bps,
+        performance_fee_bps,
+        effective_at,
+        admin: ctx.accounts.admin.key(),
+    });
+
+    Ok(())
+}
+
+/// Apply the pending fee change once its timelock has expired. Admin only.
+/// Until then, execute_trader_swap and settlement keep using the current rates.
+pub fn apply_fee_change(ctx: Context<AdminGlobalConfig>) -> Result<()> {
+    let config = &mut ctx.accounts.global_config;
+    require!(config.fee_change_effective_at != 0, ErrorCode::NoPendingFeeChange);
+    require!(
+        Clock::get()?.unix_timestamp >= config.fee_change_effective_at,
+        ErrorCode::TimelockNotExpired
+    );
+
+    let old_platform_fee_bps = config.platform_fee_bps;
+    let old_performance_fee_bps = config.performance_fee_bps;
+    config.platform_fee_bps = config.pending_platform_fee_bps;
+    config.performance_fee_bps = config.pending_performance_fee_bps;
+    config.pending_platform_fee_bps = 0;
+    config.pending_performance_fee_bps = 0;
+    config.fee_change_effective_at = 0;
+    msg!("Fee config updated. Platform: {} bps, Performance: {} bps", config.platform_fee_bps, config.performance_fee_bps);
+
+    emit!(FeeConfigUpdated {
+        old_platform_fee_bps,
+        new_platform_fee_bps: config.platform_fee_bps,
+        old_performance_fee_bps,
+        new_performance_fee_bps: config.performance_fee_bps,
+        admin: ctx.accounts.admin.key(),
+    });
+
+    Ok(())
+}
+
+/// Propose a new admin. Admin only.
+/// The current admin stays in control until the new admin signs accept_admin.
+pub fn propose_admin(ctx: Context<AdminGlobalConfig>, new_admin: Pubkey) -> Result<()> {
+    let config = &mut ctx.accounts.global_config;
+    config.pending_admin = Some(new_admin);
+    msg!("Admin transfer proposed. Pending admin: {}", new_admin);
+
+    emit!(AdminTransferProposed {
+        admin: config.admin,
+        pending_admin: new_admin,
+    });
+
+    Ok(())
+}
+
+/// Accept the admin role. Must be signed by the pending admin.
+/// A guardian still set to the outgoing admin moves to the new admin.
+pub fn accept_admin(ctx: Context<AcceptAdmin>) -> Result<()> {
+    let config = &mut ctx.accounts.global_config;
+    let old_admin = config.admin;
+    config.admin = ctx.accounts.pending_admin.key();
+    config.pending_admin = None;
+    msg!("Admin transferred from {} to {}", old_admin, config.admin);
+
+    emit!(AdminTransferred {
+        old_admin,
+        new_admin: config.admin,
+    });
+
+    // The outgoing admin must not keep the power to halt trading
+    if config.guardian == old_admin {
+        config.guardian = config.admin;
+        msg!("Guardian updated from {} to {}", old_admin, config.guardian);
+
+        emit!(GuardianUpdated {
+            old_guardian: old_admin,
+            new_guardian: config.guardian,
+            admin: config.admin,
+        });
+    }
+
+    Ok(())
+}
+
+/// Approve a swap program for execute_trader_swap CPIs. Admin only.
+pub fn add_swap_program(ctx: Context<AdminGlobalConfig>, program_id: Pubkey) -> Result<()> {
+    let config = &mut ctx.accounts.global_config;
+    if !config.approved_swap_programs.contains(&program_id) {
+        require!(
+            config.approved_swap_programs.len() < MAX_SWAP_PROGRAMS,
+            ErrorCode::SwapProgramListFull
+        );
+        config.approved_swap_programs.push(program_id);
+        msg!("Approved swap program: {}", program_id);
+
+        emit!(SwapProgramUpdated {
+            program_id,
+            approved: true,
+            admin: ctx.accounts.admin.key(),
+        });
+    }
+    Ok(())
+}
+
+/// Revoke a swap program. Admin only.
+pub fn remove_swap_program(ctx: Context<AdminGlobalConfig>, program_id: Pubkey) -> Result<()> {
+    let config = &mut ctx.accounts.global_config;
+    if let Some(pos) = config.approved_swap_programs.iter().position(|x| *x == program_id) {
+        config.approved_swap_programs.remove(pos);
+        msg!("Revoked swap program: {}", program_id);
+
+        emit!(SwapProgramUpdated {
+            program_id,
+            approved: false,
+            admin: ctx.accounts.admin.key(),
+        });
+    }
+    Ok(())
+}
+
+/// Add a protocol-wide default allowed mint. Admin only.
+/// Applies to vaults whose own allowed_mints list is empty.
+pub fn add_default_mint(ctx: Context<AdminGlobalConfig>, mint: Pubkey) -> Result<()> {
+    let config = &mut ctx.accounts.global_config;
+    if !config.default_allowed_mints.contains(&mint) {
+        require!(
+            config.default_allowed_mints.len() < MAX_DEFAULT_MINTS,
+            ErrorCode::DefaultMintListFull
+        );
+        config.default_allowed_mints.push(mint);
+        msg!("Added default allowed mint: {}", mint);
+
+        emit!(DefaultMintUpdated {
+            mint,
+            allowed: true,
+            admin: ctx.accounts.admin.key(),
+        });
+    }
+    Ok(())
+}
+
+/// Remove a protocol-wide default allowed mint. Admin only.
+pub fn remove_default_mint(ctx: Context<AdminGlobalConfig>, mint: Pubkey) -> Result<()> {
+    let config = &mut ctx.accounts.global_config;
+    if let Some(pos) = config.default_allowed_mints.iter().position(|x| *x == mint) {
+        config.default_allowed_mints.remove(pos);
+        msg!("Removed default allowed mint: {}", mint);
+
+        emit!(DefaultMintUpdated {
+            mint,
+            allowed: false,
+            admin: ctx.accounts.admin.key(),
+        });
+    }
+    Ok(())
+}
+
+/// Set the guardian allowed to halt trading. Admin only.
+pub fn set_guardian(ctx: Context<AdminGlobalConfig>, new_guardian: Pubkey) -> Result<()> {
+    let config = &mut ctx.accounts.global_config;
+    let old_guardian = config.guardian;
+    config.guardian = new_guardian;
+    msg!("Guardian updated from {} to {}", old_guardian, new_guardian);
+
+    emit!(GuardianUpdated {
+        old_guardian,
+        new_guardian,
+        admin: ctx.accounts.admin.key(),
+    });
+
+    Ok(())
+}
+
+/// Emergency halt of all backend trading. Guardian or admin.
+pub fn halt_trading(ctx: Context<GuardianGlobalConfig>) -> Result<()> {
+    let config = &mut ctx.accounts.global_config;
+    config.trading_halted = true;
+    msg!("Protocol trading halted by {}", ctx.accounts.signer.key());
+
+    emit!(TradingHaltUpdated {
+        halted: true,
+        signer: ctx.accounts.signer.key(),
+    });
+
+    Ok(())
+}
+
+/// Resume backend trading after a halt. Admin only.
+pub fn resume_trading(ctx: Context<AdminGlobalConfig>) -> Result<()> {
+    let config = &mut ctx.accounts.global_config;
+    config.trading_halted = false;
+    msg!("Protocol trading resumed by {}", ctx.accounts.admin.key());
+
+    emit!(TradingHaltUpdated {
+        halted: false,
+        signer: ctx.accounts.admin.key(),
+    });
+
+    Ok(())
+}
+
 #[derive(Accounts)]
 pub struct InitializeGlobalConfig<'info> {
     #[account(
@@ -59,6 +367,22 @@ pub struct InitializeGlobalConfig<'info> {
     pub system_program: Program<'info, System>,
 }
 
+#[derive(Accounts)]
+pub struct MigrateGlobalConfig<'info> {
+    /// CHECK: Legacy layout cannot be deserialized as GlobalConfig.
+    /// Discriminator and admin are validated in the instruction.
+    #[account(
+        mut,
+        seeds = [b"global_config"],
+        bump,
+        owner = crate::ID
+    )]
+    pub global_config: UncheckedAccount<'info>,
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
 #[derive(Accounts)]
 pub struct AdminGlobalConfig<'info> {
     #[account(
@@ -71,6 +395,51 @@ pub struct AdminGlobalConfig<'info> {
     pub admin: Signer<'info>,
 }
 
+#[derive(Accounts)]
+pub struct GuardianGlobalConfig<'info> {
+    #[account(
+        mut,
+        seeds = [b"global_config"],
+        bump,
+        constraint = signer.key() == global_config.guardian
+            || signer.key() == global_config.admin @ ErrorCode::Unauthorized
+    )]
+    pub global_config: Account<'info, GlobalConfig>,
+    pub signer: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct AcceptAdmin<'info> {
+    #[account(
+        mut,
+        seeds = [b"global_config"],
+        bump,
+        constraint = global_config.pending_admin == Some(pending_admin.key()) @ ErrorCode::Unauthorized
+    )]
+    pub global_config: Account<'info, GlobalConfig>,
+    pub pending_admin: Signer<'info>,
+}
+
+#[derive(Accounts)]
+#[instruction(mint: Pubkey)]
+pub struct AddAllowedMint<'info> {
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    #[account(
+        mut,
+        has_one = owner @ ErrorCode::Unauthorized,
+        seeds = [b"user_vault_v1", owner.key().as_ref()],
+        bump = vault.bump,
+        realloc = vault.space_with_mint(&mint),
+        realloc::payer = owner,
+        realloc::zero = false
+    )]
+    pub vault: Account<'info, UserVault>,
+
+    pub system_program: Program<'info, System>,
+}
+
 #[derive(Accounts)]
 pub struct ManageWhitelist<'info> {
     #[account(mut)]
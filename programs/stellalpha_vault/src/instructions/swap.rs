@@ -1,21 +1,23 @@
 use anchor_lang::prelude::*;
+use anchor_spl::associated_token::get_associated_token_address;
 use anchor_spl::token::{self, Token, TokenAccount, Transfer};
 use crate::state::*;
 use crate::errors::ErrorCode;
 use crate::constants::PLATFORM_FEE_WALLET;
-use std::str::FromStr;
 
 // =========================================================================
 // LEGACY PATH — DEPRECATED
 // Disabled by GlobalConfig.legacy_trading_enabled
 // DO NOT EXTEND. All new execution must use TraderState (execute_trader_swap).
 // =========================================================================
+#[allow(clippy::manual_saturating_arithmetic)] // Legacy code kept as-is
 pub fn execute_swap(ctx: Context<ExecuteSwap>, amount_in: u64, min_amount_out: u64) -> Result<()> {
     // Gate: Legacy trading must be enabled
     require!(
         ctx.accounts.global_config.legacy_trading_enabled,
         ErrorCode::LegacyTradingDisabled
     );
+    require!(!ctx.accounts.global_config.trading_halted, ErrorCode::TradingHalted);
 
     let vault = &ctx.accounts.vault;
     require!(!vault.is_paused, ErrorCode::Paused);
@@ -170,18 +172,35 @@ pub fn execute_swap(ctx: Context<ExecuteSwap>, amount_in: u64, min_amount_out: u
 /// amount_in: Total amount to spend, including platform fee.
 /// min_amount_out: Minimum amount to receive (slippage protection).
 /// data: Opaque data blob for Jupiter swap instruction.
-pub fn execute_trader_swap(ctx: Context<ExecuteTraderSwap>, amount_in: u64, min_amount_out: u64, data: Vec<u8>) -> Result<()> {
+pub fn execute_trader_swap(
+    ctx: Context<ExecuteTraderSwap>,
+    amount_in: u64,
+    min_amount_out: u64,
+    expected_amount_out: u64,
+    data: Vec<u8>,
+) -> Result<()> {
     let trader_state = &mut ctx.accounts.trader_state;
     let vault = &ctx.accounts.vault;
     let global_config = &ctx.accounts.global_config;
 
     // 1. Auth & Status Checks
-    require!(!trader_state.is_paused, ErrorCode::TraderPaused);
+    require!(!global_config.trading_halted, ErrorCode::TradingHalted);
+    require!(!vault.is_paused, ErrorCode::Paused);
     require!(vault.authority == ctx.accounts.authority.key(), ErrorCode::Unauthorized);
-    // Phase 7C: Allow swaps after initialization
+    // Phase 7C: Allow swaps during sync phase, when active, or to unwind while settling
+    match trader_state.status {
+        TraderStatus::Active | TraderStatus::Settling => {}
+        TraderStatus::Syncing => {
+            require!(Clock::get()?.unix_timestamp <= trader_state.sync_deadline, ErrorCode::SyncExpired);
+        }
+        status => status.require_active()?,
+    }
+
+    // Only admin-approved swap programs may receive the TraderState PDA signature.
+    let jupiter_program_id = ctx.accounts.jupiter_program.key();
     require!(
-        trader_state.is_initialized,
-        ErrorCode::TraderNotInitialized
+        global_config.approved_swap_programs.contains(&jupiter_program_id),
+        ErrorCode::SwapProgramNotApproved
     );
 
     // 2. Topology Checks
@@ -195,6 +214,20 @@ pub fn execute_trader_swap(ctx: Context<ExecuteTraderSwap>, amount_in: u64, min_
     let is_trader_owned_out = ctx.accounts.output_token_account.owner == trader_state.key();
     require!(is_trader_owned_in, ErrorCode::InvalidTokenAccountOwner);
     require!(is_trader_owned_out, ErrorCode::InvalidTokenAccountOwner);
+    // ...and be its ATAs, the accounts settlement and position tracking look at
+    require!(
+        ctx.accounts.input_token_account.key() == get_associated_token_address(&trader_state.key(), &input_mint),
+        ErrorCode::NotTraderAta
+    );
+    require!(
+        ctx.accounts.output_token_account.key() == get_associated_token_address(&trader_state.key(), &output_mint),
+        ErrorCode::NotTraderAta
+    );
+
+    // Settlement only unwinds into the Base Asset
+    if trader_state.status == TraderStatus::Settling {
+        require!(output_mint == base_mint, ErrorCode::TraderSettling);
+    }
 
     // Phase 7C: Relaxed topology for token→token swaps
     // Original: at least one side must be Base Asset
@@ -206,6 +239,25 @@ pub fn execute_trader_swap(ctx: Context<ExecuteTraderSwap>, amount_in: u64, min_
         ErrorCode::InvalidSwapTopology
     );
 
+    // Whitelist Check: vault.allowed_mints (or GlobalConfig defaults if empty) applies
+    // to every TraderState under this vault. Only buying is gated, so a position
+    // whose mint is later delisted can still be sold.
+    require!(vault.is_mint_allowed(&output_mint, &global_config.default_allowed_mints), ErrorCode::TokenNotAllowed);
+
+    // A swap must change what is held: same-mint swaps would only pay the fee.
+    require!(input_mint != output_mint, ErrorCode::InvalidSwapTopology);
+    // Non-base sides must carry their TraderPosition (one record per mint).
+    require!(input_mint == base_mint || ctx.accounts.input_position.is_some(), ErrorCode::PositionAccountMissing);
+    require!(output_mint == base_mint || ctx.accounts.output_position.is_some(), ErrorCode::PositionAccountMissing);
+
+    // Owner-configured risk limits (trade size, slippage tolerance)
+    // Non-base inputs are sized at the cost basis they would release
+    let trade_size = match ctx.accounts.input_position.as_ref() {
+        Some(position) if input_mint != base_mint => position.cost_of(amount_in)?,
+        _ => amount_in,
+    };
+    trader_state.check_risk_limits(trade_size, min_amount_out, expected_amount_out)?;
+
     // 3. Platform Fee
     // Ensure fee destination is correct (admin's token account)
     require!(ctx.accounts.platform_fee_account.owner == global_config.admin, ErrorCode::InvalidFeeDestination);
@@ -225,10 +277,12 @@ pub fn execute_trader_swap(ctx: Context<ExecuteTraderSwap>, amount_in: u64, min_
 
     // Transfer Fee
     if fee > 0 {
+        let allocation_seed = trader_state.allocation_seed();
         let seeds = &[
             b"trader_state",
             trader_state.owner.as_ref(),
             trader_state.trader.as_ref(),
+            allocation_seed.as_slice(),
             &[trader_state.bump],
         ];
         let signer = &[&seeds[..]];
@@ -248,10 +302,12 @@ pub fn execute_trader_swap(ctx: Context<ExecuteTraderSwap>, amount_in: u64, min_
     }
 
     // 4. Jupiter CPI
+    let allocation_seed = trader_state.allocation_seed();
     let seeds = &[
         b"trader_state",
         trader_state.owner.as_ref(),
         trader_state.trader.as_ref(),
+        allocation_seed.as_slice(),
         &[trader_state.bump],
     ];
     let signer = &[&seeds[..]];
@@ -263,56 +319,37 @@ pub fn execute_trader_swap(ctx: Context<ExecuteTraderSwap>, amount_in: u64, min_
     let balance_in_before = ctx.accounts.input_token_account.amount;
     let balance_out_before = ctx.accounts.output_token_account.amount;
 
-    // Devnet Mock (Memo) vs Mainnet (Jupiter)
-    let jupiter_program_id = ctx.accounts.jupiter_program.key();
-    let memo_program_id = Pubkey::from_str("MemoSq4gqABAXKb96qnH8TysNcWxMyWCqXgDLGmfcQb").unwrap();
-    
-    if jupiter_program_id == memo_program_id {
-            // Mock Swap via Memo
-            msg!("Devnet: Simulating swap via Memo");
-            // Simulate token movement if mints match (test only)
-            if input_mint == output_mint {
-                let cpi_accounts = Transfer {
-                from: ctx.accounts.input_token_account.to_account_info(),
-                to: ctx.accounts.output_token_account.to_account_info(),
-                authority: trader_state.to_account_info(), // Use ref
-            };
-            let cpi_ctx = CpiContext::new_with_signer(ctx.accounts.token_program.to_account_info(), cpi_accounts, signer);
-            token::transfer(cpi_ctx, swap_amount)?;
-            }
-    } else {
-            // Real Jupiter CPI (or external swap program)
-            // IMPORTANT: Mark TraderState PDA as signer in the CPI instruction.
-            // This is required because invoke_signed signs for this PDA, and the
-            // instruction's AccountMeta must have is_signer=true to match.
-            // AUDIT: PDA signing via invoke_signed accepted; does not grant backend private key; invariants remain.
-            let trader_state_key = trader_state.key();
-            let remaining_accounts: Vec<anchor_lang::solana_program::instruction::AccountMeta> = ctx.remaining_accounts.iter().map(|acc| {
-            // If this account is the TraderState PDA, mark as signer (will be signed via invoke_signed)
-            let is_signer = if *acc.key == trader_state_key {
-                true
-            } else {
-                acc.is_signer
-            };
-            if acc.is_writable {
-                anchor_lang::solana_program::instruction::AccountMeta::new(*acc.key, is_signer)
-            } else {
-                anchor_lang::solana_program::instruction::AccountMeta::new_readonly(*acc.key, is_signer)
-            }
-            }).collect();
-
-            let ix = anchor_lang::solana_program::instruction::Instruction {
-            program_id: jupiter_program_id,
-            accounts: remaining_accounts,
-            data: data,
+    // Jupiter CPI (or another approved swap program)
+    // IMPORTANT: Mark TraderState PDA as signer in the CPI instruction.
+    // This is required because invoke_signed signs for this PDA, and the
+    // instruction's AccountMeta must have is_signer=true to match.
+    // AUDIT: PDA signing via invoke_signed accepted; does not grant backend private key; invariants remain.
+    let trader_state_key = trader_state.key();
+    let remaining_accounts: Vec<anchor_lang::solana_program::instruction::AccountMeta> = ctx.remaining_accounts.iter().map(|acc| {
+        // If this account is the TraderState PDA, mark as signer (will be signed via invoke_signed)
+        let is_signer = if *acc.key == trader_state_key {
+            true
+        } else {
+            acc.is_signer
         };
-        
-        anchor_lang::solana_program::program::invoke_signed(
-            &ix,
-            ctx.remaining_accounts,
-            signer
-        )?;
-    }
+        if acc.is_writable {
+            anchor_lang::solana_program::instruction::AccountMeta::new(*acc.key, is_signer)
+        } else {
+            anchor_lang::solana_program::instruction::AccountMeta::new_readonly(*acc.key, is_signer)
+        }
+    }).collect();
+
+    let ix = anchor_lang::solana_program::instruction::Instruction {
+        program_id: jupiter_program_id,
+        accounts: remaining_accounts,
+        data,
+    };
+
+    anchor_lang::solana_program::program::invoke_signed(
+        &ix,
+        ctx.remaining_accounts,
+        signer
+    )?;
 
     // 5. Post-Swap Balance Check
     ctx.accounts.input_token_account.reload()?;
@@ -326,22 +363,86 @@ pub fn execute_trader_swap(ctx: Context<ExecuteTraderSwap>, amount_in: u64, min_
     // balance_in_after = Final.
     // spent = (Initial - Fee) - Final.
     // We ensure spent <= swap_amount.
-    let amount_spent = balance_in_before.checked_sub(balance_in_after).unwrap();
-    let amount_received = balance_out_after.checked_sub(balance_out_before).unwrap();
+    // A balance moving the other way counts as nothing spent or received.
+    let amount_spent = balance_in_before.saturating_sub(balance_in_after);
+    let amount_received = balance_out_after.saturating_sub(balance_out_before);
 
     require!(amount_spent <= swap_amount, ErrorCode::FeeEvasion);
     require!(amount_received >= min_amount_out, ErrorCode::SlippageExceeded);
 
-    // Phase 4: TraderState Accounting (Tx Fee Only)
-    // Update current_value ONLY when swapping back to Base Asset.
-    // We assume 'amount_received' represents the full value of the position being exited 
-    // back into the Base Asset. Performance fees/HWM are explicitly deferred.
-    if output_mint == base_mint {
-        trader_state.current_value = amount_received;
-        msg!("Updated TraderState current_value: {}", trader_state.current_value);
+    // Rate limits: volume is counted on the Base Asset leg of the swap.
+    // Portfolio sync swaps are not copy trades and do not count.
+    if !trader_state.is_syncing() {
+        let base_volume = if input_mint == base_mint {
+            amount_in
+        } else if output_mint == base_mint {
+            amount_received
+        } else {
+            0
+        };
+        trader_state.record_swap(Clock::get()?.unix_timestamp, base_volume)?;
     }
 
+    // TraderState Accounting (cost basis)
+    // current_value = base holdings + cost basis of open positions. PnL is
+    // realized only when a position is exited back into the Base Asset.
+    let input_position = if input_mint == base_mint {
+        None
+    } else {
+        Some(&mut **ctx.accounts.input_position.as_mut().ok_or(ErrorCode::PositionAccountMissing)?)
+    };
+    let output_position = if output_mint == base_mint {
+        None
+    } else {
+        Some(&mut **ctx.accounts.output_position.as_mut().ok_or(ErrorCode::PositionAccountMissing)?)
+    };
+    let realized = trader_state.apply_swap(input_position, output_position, amount_spent, fee, amount_received)?;
+    msg!("Updated TraderState current_value: {} (realized: {})", trader_state.current_value, realized);
+
+    let is_sync = trader_state.is_syncing();
+    ctx.accounts.trade_journal.load_mut()?.append(JournalEntry {
+        slot: Clock::get()?.slot,
+        input_mint,
+        output_mint,
+        amount_spent,
+        amount_received,
+        fee,
+        is_sync: is_sync as u8,
+        ..Default::default()
+    });
+
+    let swap_event = TraderSwapExecuted {
+        trader_state: trader_state.key(),
+        input_mint,
+        output_mint,
+        amount_in,
+        fee,
+        amount_spent,
+        amount_received,
+        realized_pnl: realized,
+        current_value: trader_state.current_value,
+        is_sync,
+    };
+
+    // Drawdown stop is evaluated on realized value (swaps back into Base Asset)
+    let drawdown_event = if output_mint == base_mint && trader_state.record_base_value()? {
+        msg!("Drawdown stop triggered. TraderState paused.");
+        Some(DrawdownStopTriggered {
+            trader_state: trader_state.key(),
+            peak_value: trader_state.peak_value,
+            current_value: trader_state.current_value,
+            max_drawdown_bps: trader_state.max_drawdown_bps,
+        })
+    } else {
+        None
+    };
+
     msg!("Swap Success. In: {}, Out: {}", amount_spent, amount_received);
+
+    emit_event!(ctx, swap_event);
+    if let Some(event) = drawdown_event {
+        emit_event!(ctx, event);
+    }
     Ok(())
 }
 
@@ -385,6 +486,7 @@ pub struct ExecuteSwap<'info> {
     pub token_program: Program<'info, Token>,
 }
 
+#[cfg_attr(feature = "event-cpi", event_cpi)]
 #[derive(Accounts)]
 pub struct ExecuteTraderSwap<'info> {
     #[account(mut)]
@@ -400,16 +502,28 @@ pub struct ExecuteTraderSwap<'info> {
     #[account(
         mut,
         has_one = vault @ ErrorCode::Unauthorized,
-        seeds = [b"trader_state", trader_state.owner.as_ref(), trader_state.trader.as_ref()],
+        seeds = [
+            b"trader_state",
+            trader_state.owner.as_ref(),
+            trader_state.trader.as_ref(),
+            trader_state.allocation_seed().as_slice()
+        ],
         bump = trader_state.bump
     )]
     pub trader_state: Account<'info, TraderState>,
 
+    #[account(
+        mut,
+        seeds = [b"trade_journal", trader_state.key().as_ref()],
+        bump = trade_journal.load()?.bump
+    )]
+    pub trade_journal: AccountLoader<'info, TradeJournal>,
+
     #[account(mut)]
-    pub input_token_account: Account<'info, TokenAccount>, // Owned by TraderState
+    pub input_token_account: Account<'info, TokenAccount>, // TraderState ATA for the input mint
 
     #[account(mut)]
-    pub output_token_account: Account<'info, TokenAccount>, // Owned by TraderState
+    pub output_token_account: Account<'info, TokenAccount>, // TraderState ATA for the output mint
 
     #[account(mut)]
     pub platform_fee_account: Account<'info, TokenAccount>,
@@ -420,7 +534,7 @@ pub struct ExecuteTraderSwap<'info> {
     )]
     pub global_config: Account<'info, GlobalConfig>,
 
-    /// CHECK: Validated by Jupiter CPI or Memo check
+    /// CHECK: Validated against global_config.approved_swap_programs
     pub jupiter_program: UncheckedAccount<'info>,
     
     pub token_program: Program<'info, Token>,
@@ -428,4 +542,20 @@ pub struct ExecuteTraderSwap<'info> {
     /// CHECK: Instructions sysvar for introspection
     #[account(address = sysvar::instructions::ID)]
     pub instructions: UncheckedAccount<'info>,
+
+    /// Required when the input mint is not the Base Asset.
+    #[account(
+        mut,
+        seeds = [b"trader_position", trader_state.key().as_ref(), input_token_account.mint.as_ref()],
+        bump = input_position.bump
+    )]
+    pub input_position: Option<Account<'info, TraderPosition>>,
+
+    /// Required when the output mint is not the Base Asset.
+    #[account(
+        mut,
+        seeds = [b"trader_position", trader_state.key().as_ref(), output_token_account.mint.as_ref()],
+        bump = output_position.bump
+    )]
+    pub output_position: Option<Account<'info, TraderPosition>>,
 }
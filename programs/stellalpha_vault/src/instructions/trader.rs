@@ -1,24 +1,39 @@
 use anchor_lang::prelude::*;
 use anchor_spl::token::{self, Token, TokenAccount, Transfer, Mint, CloseAccount};
-use anchor_spl::associated_token::AssociatedToken;
+use anchor_spl::associated_token::{get_associated_token_address, AssociatedToken};
+use anchor_lang::system_program;
 use crate::state::*;
 use crate::errors::ErrorCode;
+use crate::constants::{MIN_CRYSTALLIZATION_INTERVAL, SYNC_DURATION};
+
+/// Create a TraderState allocation funded from the UserVault.
+/// allocation_id lets one owner follow the same trader with several independent
+/// allocations. Allocation 0 is the default (pre-allocation-id) address.
+pub fn create_trader_state(ctx: Context<CreateTraderState>, amount: u64, allocation_id: u16) -> Result<()> {
+    require!(!ctx.accounts.global_config.trading_halted, ErrorCode::TradingHalted);
+    require!(!ctx.accounts.vault.is_paused, ErrorCode::Paused);
 
-pub fn create_trader_state(ctx: Context<CreateTraderState>, amount: u64) -> Result<()> {
     let trader_state = &mut ctx.accounts.trader_state;
     trader_state.owner = ctx.accounts.owner.key();
     trader_state.trader = ctx.accounts.trader.key();
     trader_state.vault = ctx.accounts.vault.key();
     trader_state.bump = ctx.bumps.trader_state;
+    trader_state.allocation_id = allocation_id;
     
     trader_state.current_value = amount;
     trader_state.high_water_mark = amount;
+    trader_state.peak_value = amount;
     trader_state.cumulative_profit = 0;
-    trader_state.is_paused = false;
-    trader_state.is_settled = false;
     
     // Phase 7C: Default to uninitialized
-    trader_state.is_initialized = false;
+    trader_state.status = TraderStatus::Created;
+    trader_state.last_crystallized_at = Clock::get()?.unix_timestamp;
+
+    {
+        let mut journal = ctx.accounts.trade_journal.load_init()?;
+        journal.trader_state = trader_state.key();
+        journal.bump = ctx.bumps.trade_journal;
+    }
 
     // Transfer initial funding from UserVault to TraderState
     let seeds = &[
@@ -37,27 +52,180 @@ pub fn create_trader_state(ctx: Context<CreateTraderState>, amount: u64) -> Resu
     let cpi_ctx = CpiContext::new_with_signer(cpi_program, cpi_accounts, signer);
     token::transfer(cpi_ctx, amount)?;
 
-    msg!("Created TraderState for trader: {}. Funded with: {}", trader_state.trader, amount);
+    msg!(
+        "Created TraderState for trader: {} (allocation {}). Funded with: {}",
+        trader_state.trader, allocation_id, amount
+    );
+
+    emit!(TraderStateCreated {
+        trader_state: trader_state.key(),
+        owner: trader_state.owner,
+        trader: trader_state.trader,
+        vault: trader_state.vault,
+        allocation_id,
+        amount,
+    });
+
+    Ok(())
+}
+
+/// Top up an existing TraderState with Base Asset from the UserVault.
+/// Owner-only. Added capital raises current_value and high_water_mark equally,
+/// so it is never charged performance fees.
+pub fn fund_trader_state(ctx: Context<FundTraderState>, amount: u64) -> Result<()> {
+    require!(!ctx.accounts.global_config.trading_halted, ErrorCode::TradingHalted);
+    require!(!ctx.accounts.vault.is_paused, ErrorCode::Paused);
+    // A paused TraderState is judged by the phase it will resume into
+    let phase = match ctx.accounts.trader_state.status {
+        TraderStatus::Paused => ctx.accounts.trader_state.resume_status,
+        status => status,
+    };
+    match phase {
+        TraderStatus::Settling => return err!(ErrorCode::TraderSettling),
+        TraderStatus::Settled => return err!(ErrorCode::AlreadySettled),
+        TraderStatus::Closed => return err!(ErrorCode::TraderClosed),
+        _ => {}
+    }
+
+    let seeds = &[
+        b"user_vault_v1",
+        ctx.accounts.owner.key.as_ref(),
+        &[ctx.accounts.vault.bump],
+    ];
+    let signer = &[&seeds[..]];
+
+    let cpi_accounts = Transfer {
+        from: ctx.accounts.vault_token_account.to_account_info(),
+        to: ctx.accounts.trader_token_account.to_account_info(),
+        authority: ctx.accounts.vault.to_account_info(),
+    };
+    let cpi_program = ctx.accounts.token_program.to_account_info();
+    let cpi_ctx = CpiContext::new_with_signer(cpi_program, cpi_accounts, signer);
+    token::transfer(cpi_ctx, amount)?;
+
+    let trader_state = &mut ctx.accounts.trader_state;
+    trader_state.current_value = trader_state.current_value.checked_add(amount).ok_or(ErrorCode::MathOverflow)?;
+    trader_state.high_water_mark = trader_state.high_water_mark.checked_add(amount).ok_or(ErrorCode::MathOverflow)?;
+    trader_state.peak_value = trader_state.peak_value.checked_add(amount).ok_or(ErrorCode::MathOverflow)?;
+
+    msg!("Funded TraderState with: {}. Current value: {}", amount, trader_state.current_value);
+
+    emit!(TraderStateFunded {
+        trader_state: trader_state.key(),
+        amount,
+        current_value: trader_state.current_value,
+        high_water_mark: trader_state.high_water_mark,
+    });
+
     Ok(())
 }
 
 pub fn pause_trader_state(ctx: Context<UpdateTraderState>) -> Result<()> {
     let trader_state = &mut ctx.accounts.trader_state;
-    trader_state.is_paused = true;
+    if trader_state.status != TraderStatus::Paused {
+        trader_state.transition(TraderStatus::Paused)?;
+        emit!(TraderStatePaused {
+            trader_state: trader_state.key(),
+            resume_status: trader_state.resume_status,
+        });
+    }
     msg!("TraderState paused.");
     Ok(())
 }
 
 pub fn resume_trader_state(ctx: Context<UpdateTraderState>) -> Result<()> {
     let trader_state = &mut ctx.accounts.trader_state;
-    trader_state.is_paused = false;
+    if trader_state.status != TraderStatus::Active {
+        require!(
+            trader_state.status == TraderStatus::Paused,
+            trader_state.status.transition_error(TraderStatus::Active)
+        );
+        let resume_status = trader_state.resume_status;
+        trader_state.transition(resume_status)?;
+        emit!(TraderStateResumed {
+            trader_state: trader_state.key(),
+            status: resume_status,
+        });
+    }
     msg!("TraderState resumed.");
     Ok(())
 }
 
+/// Start unwinding a TraderState to Base Asset. Owner only.
+/// While Settling, the backend may only swap into the Base Asset; settle_trader_state
+/// then moves it to Settled.
+pub fn begin_trader_settlement(ctx: Context<UpdateTraderState>) -> Result<()> {
+    let trader_state = &mut ctx.accounts.trader_state;
+    let old_status = trader_state.status;
+    trader_state.transition(TraderStatus::Settling)?;
+    msg!("TraderState settling.");
+
+    emit!(TraderStatusChanged {
+        trader_state: trader_state.key(),
+        old_status,
+        new_status: TraderStatus::Settling,
+    });
+
+    Ok(())
+}
+
+fn emit_limits_updated(trader_state: &Account<TraderState>) {
+    emit!(TraderLimitsUpdated {
+        trader_state: trader_state.key(),
+        max_trade_bps: trader_state.max_trade_bps,
+        max_slippage_bps: trader_state.max_slippage_bps,
+        max_drawdown_bps: trader_state.max_drawdown_bps,
+        max_swaps_per_window: trader_state.max_swaps_per_window,
+        max_volume_per_window: trader_state.max_volume_per_window,
+    });
+}
+
+/// Configure risk limits for a TraderState. Owner only. 0 disables a limit.
+pub fn set_risk_limits(ctx: Context<UpdateTraderState>, max_trade_bps: u16, max_slippage_bps: u16) -> Result<()> {
+    require!(max_trade_bps <= 10000, ErrorCode::InvalidRiskLimit);
+    require!(max_slippage_bps <= 10000, ErrorCode::InvalidRiskLimit);
+
+    let trader_state = &mut ctx.accounts.trader_state;
+    trader_state.max_trade_bps = max_trade_bps;
+    trader_state.max_slippage_bps = max_slippage_bps;
+    msg!("Risk limits set: max_trade_bps {}, max_slippage_bps {}", max_trade_bps, max_slippage_bps);
+    emit_limits_updated(trader_state);
+    Ok(())
+}
+
+/// Configure the drawdown stop for a TraderState. Owner only. 0 disables it.
+pub fn set_max_drawdown(ctx: Context<UpdateTraderState>, max_drawdown_bps: u16) -> Result<()> {
+    require!(max_drawdown_bps <= 10000, ErrorCode::InvalidRiskLimit);
+
+    let trader_state = &mut ctx.accounts.trader_state;
+    trader_state.max_drawdown_bps = max_drawdown_bps;
+    msg!("Max drawdown set: {} bps", max_drawdown_bps);
+    emit_limits_updated(trader_state);
+    Ok(())
+}
+
+/// Configure per-window swap count and volume ceilings. Owner only. 0 disables a limit.
+/// Windows are fixed, not rolling: counters reset RATE_LIMIT_WINDOW after the
+/// first swap of a window, so up to twice a ceiling can pass around a reset.
+pub fn set_rate_limits(ctx: Context<UpdateTraderState>, max_swaps_per_window: u32, max_volume_per_window: u64) -> Result<()> {
+    let trader_state = &mut ctx.accounts.trader_state;
+    trader_state.max_swaps_per_window = max_swaps_per_window;
+    trader_state.max_volume_per_window = max_volume_per_window;
+    msg!("Rate limits set: {} swaps, {} volume per window", max_swaps_per_window, max_volume_per_window);
+    emit_limits_updated(trader_state);
+    Ok(())
+}
+
+/// Close a settled TraderState and refund its Base Asset to the UserVault.
+/// Owner-only. Requires Settled, so the performance fee has been charged, and
+/// every non-base ATA closed, so nothing is stranded.
 pub fn close_trader_state(ctx: Context<CloseTraderState>) -> Result<()> {
-    let trader_state = &ctx.accounts.trader_state;
-    require!(trader_state.is_paused, ErrorCode::TraderNotPaused);
+    let trader_state = &mut ctx.accounts.trader_state;
+    require!(trader_state.status == TraderStatus::Settled, ErrorCode::NotSettled);
+    trader_state.transition(TraderStatus::Closed)?;
+    // Closing with open positions would strand their tokens
+    require!(trader_state.open_positions == 0, ErrorCode::PositionsOpen);
+    require!(trader_state.position_count == 0, ErrorCode::PositionAccountsRemain);
 
     let amount = ctx.accounts.trader_token_account.amount;
 
@@ -69,10 +237,12 @@ pub fn close_trader_state(ctx: Context<CloseTraderState>) -> Result<()> {
     };
 
     // Close TraderState ATA
+    let allocation_seed = trader_state.allocation_seed();
     let seeds = &[
         b"trader_state",
         trader_state.owner.as_ref(),
         trader_state.trader.as_ref(),
+        allocation_seed.as_slice(),
         &[trader_state.bump],
     ];
     let signer = &[&seeds[..]];
@@ -92,6 +262,13 @@ pub fn close_trader_state(ctx: Context<CloseTraderState>) -> Result<()> {
     token::close_account(cpi_ctx_close)?;
 
     msg!("Closed TraderState and refunded.");
+
+    emit!(TraderStateClosed {
+        trader_state: trader_state.key(),
+        owner: trader_state.owner,
+        amount,
+    });
+
     Ok(())
 }
 
@@ -100,8 +277,26 @@ pub fn close_trader_state(ctx: Context<CloseTraderState>) -> Result<()> {
 // =========================================================================
 
 /// Phase 7A: Create additional token account for TraderState to hold non-base assets.
-/// Owner-only. No funds transferred.
+/// Owner-only. No funds transferred. The Base Asset cannot have a position.
 pub fn create_trader_ata(ctx: Context<CreateTraderAta>) -> Result<()> {
+    require_keys_neq!(ctx.accounts.mint.key(), ctx.accounts.vault.base_mint, ErrorCode::BaseMintPosition);
+
+    // Position record is created once, alongside the ATA
+    let position = &mut ctx.accounts.trader_position;
+    if position.trader_state == Pubkey::default() {
+        let trader_state = &mut ctx.accounts.trader_state;
+        position.trader_state = trader_state.key();
+        position.mint = ctx.accounts.mint.key();
+        position.bump = ctx.bumps.trader_position;
+        trader_state.position_count = trader_state.position_count.checked_add(1).ok_or(ErrorCode::MathOverflow)?;
+
+        emit!(TraderPositionCreated {
+            trader_state: trader_state.key(),
+            mint: position.mint,
+            position_count: trader_state.position_count,
+        });
+    }
+
     msg!(
         "Created additional TraderState ATA for mint: {}",
         ctx.accounts.mint.key()
@@ -110,7 +305,7 @@ pub fn create_trader_ata(ctx: Context<CreateTraderAta>) -> Result<()> {
 }
 
 /// Phase 7D: Mark TraderState as initialized after portfolio sync.
-/// Can be called by owner OR backend authority (one-time only).
+/// Can be called by owner OR backend authority (one-time only, from Created).
 /// NOTE: Prefer finish_trader_sync for explicit sync lifecycle.
 pub fn mark_trader_initialized(ctx: Context<MarkTraderInitialized>) -> Result<()> {
     let trader_state = &mut ctx.accounts.trader_state;
@@ -121,35 +316,146 @@ pub fn mark_trader_initialized(ctx: Context<MarkTraderInitialized>) -> Result<()
         signer == vault.owner || signer == vault.authority,
         ErrorCode::Unauthorized
     );
-    require!(!trader_state.is_initialized, ErrorCode::AlreadyInitialized);
+    require!(!ctx.accounts.global_config.trading_halted, ErrorCode::TradingHalted);
+    require!(!vault.is_paused, ErrorCode::Paused);
+    // A running sync is ended by the backend via finish_trader_sync
+    match trader_state.status {
+        TraderStatus::Created => {}
+        TraderStatus::Syncing => return err!(ErrorCode::AlreadySyncing),
+        TraderStatus::Paused => return err!(ErrorCode::TraderPaused),
+        _ => return err!(ErrorCode::AlreadyInitialized),
+    }
 
-    trader_state.is_initialized = true;
+    let old_status = trader_state.status;
+    trader_state.transition(TraderStatus::Active)?;
     
     msg!("TraderState marked as initialized by {}", signer);
+
+    emit!(TraderStatusChanged {
+        trader_state: trader_state.key(),
+        old_status,
+        new_status: TraderStatus::Active,
+    });
+
+    Ok(())
+}
+
+/// Phase 7C: Start portfolio sync phase.
+/// Backend authority only. Enables sync swaps until SYNC_DURATION elapses.
+/// INVARIANT: Cannot start sync if already initialized (irreversible).
+pub fn start_trader_sync(ctx: Context<TraderSync>) -> Result<()> {
+    let trader_state = &mut ctx.accounts.trader_state;
+
+    require!(!ctx.accounts.global_config.trading_halted, ErrorCode::TradingHalted);
+    require!(!ctx.accounts.vault.is_paused, ErrorCode::Paused);
+    let now = Clock::get()?.unix_timestamp;
+    trader_state.transition(TraderStatus::Syncing)?;
+    trader_state.sync_deadline = now.checked_add(SYNC_DURATION).ok_or(ErrorCode::MathOverflow)?;
+    msg!("TraderState sync started. Deadline: {}", trader_state.sync_deadline);
+
+    emit!(TraderStatusChanged {
+        trader_state: trader_state.key(),
+        old_status: TraderStatus::Created,
+        new_status: TraderStatus::Syncing,
+    });
+
+    Ok(())
+}
+
+/// Phase 7C: Finish portfolio sync and transition to automated trading.
+/// Backend authority only. Syncing -> Active.
+/// The synced portfolio becomes the drawdown and fee baseline.
+pub fn finish_trader_sync(ctx: Context<TraderSync>) -> Result<()> {
+    let trader_state = &mut ctx.accounts.trader_state;
+
+    require!(!ctx.accounts.global_config.trading_halted, ErrorCode::TradingHalted);
+    require!(!ctx.accounts.vault.is_paused, ErrorCode::Paused);
+    require!(trader_state.is_syncing(), ErrorCode::NotSyncing);
+
+    trader_state.transition(TraderStatus::Active)?;
+    // Sync fees are a cost of entry, not a loss to earn back before fees accrue
+    trader_state.peak_value = trader_state.current_value;
+    trader_state.high_water_mark = trader_state.current_value;
+    trader_state.last_crystallized_at = Clock::get()?.unix_timestamp;
+    msg!("TraderState sync finished. Automated trading enabled.");
+
+    emit!(TraderStatusChanged {
+        trader_state: trader_state.key(),
+        old_status: TraderStatus::Syncing,
+        new_status: TraderStatus::Active,
+    });
+
+    Ok(())
+}
+
+/// Abort a portfolio sync the backend failed to finish before its deadline.
+/// Owner only. Leaves the TraderState paused so it can be unwound and withdrawn.
+pub fn abort_trader_sync(ctx: Context<UpdateTraderState>) -> Result<()> {
+    let trader_state = &mut ctx.accounts.trader_state;
+
+    require!(trader_state.is_syncing(), ErrorCode::NotSyncing);
+    require!(
+        Clock::get()?.unix_timestamp > trader_state.sync_deadline,
+        ErrorCode::SyncDeadlineNotReached
+    );
+
+    trader_state.transition(TraderStatus::Paused)?;
+    msg!("TraderState sync aborted by owner. TraderState paused.");
+
+    emit!(TraderStatusChanged {
+        trader_state: trader_state.key(),
+        old_status: TraderStatus::Syncing,
+        new_status: TraderStatus::Paused,
+    });
+
     Ok(())
 }
 
 
 /// Phase 7.1: Close a non-base TraderState ATA to reclaim rent.
-/// Owner-only. Requires is_paused = true. ATA balance must be 0.
-/// Rent returned to owner.
+/// Owner-only. Requires Paused or Settled. Closes the matching TraderPosition
+/// (must be empty). Rent returned to owner.
+/// Tokens the position does not track (e.g. dust sent to the ATA) are swept
+/// to owner_token_account first, so a transfer cannot block the exit.
 pub fn close_trader_ata(ctx: Context<CloseTraderAtaContext>) -> Result<()> {
-    let trader_state = &ctx.accounts.trader_state;
+    let trader_state = &mut ctx.accounts.trader_state;
     
-    // Safety: Must be paused to prevent closing ATAs mid-trade
-    require!(trader_state.is_paused, ErrorCode::TraderNotPaused);
+    // Safety: Must be paused (or settled) to prevent closing ATAs mid-trade
+    require!(
+        matches!(trader_state.status, TraderStatus::Paused | TraderStatus::Settled),
+        ErrorCode::TraderNotPaused
+    );
     
-    // Safety: Cannot close if there is a balance
-    require!(ctx.accounts.trader_token_account.amount == 0, ErrorCode::NonZeroBalance);
+    // Tracked holdings are part of current_value and must be sold, not swept
+    require!(!ctx.accounts.trader_position.is_open(), ErrorCode::PositionsOpen);
 
+    let allocation_seed = trader_state.allocation_seed();
     let seeds = &[
         b"trader_state",
         trader_state.owner.as_ref(),
         trader_state.trader.as_ref(),
+        allocation_seed.as_slice(),
         &[trader_state.bump],
     ];
     let signer = &[&seeds[..]];
 
+    let swept = ctx.accounts.trader_token_account.amount;
+    if swept > 0 {
+        let owner_token_account = ctx.accounts.owner_token_account.as_ref().ok_or(ErrorCode::NonZeroBalance)?;
+        let cpi_accounts = Transfer {
+            from: ctx.accounts.trader_token_account.to_account_info(),
+            to: owner_token_account.to_account_info(),
+            authority: trader_state.to_account_info(),
+        };
+        let cpi_ctx = CpiContext::new_with_signer(
+            ctx.accounts.token_program.to_account_info(),
+            cpi_accounts,
+            signer
+        );
+        token::transfer(cpi_ctx, swept)?;
+        msg!("Swept {} untracked tokens to owner.", swept);
+    }
+
     let cpi_accounts_close = token::CloseAccount {
         account: ctx.accounts.trader_token_account.to_account_info(),
         destination: ctx.accounts.owner.to_account_info(), // Rent returned to owner
@@ -162,47 +468,223 @@ pub fn close_trader_ata(ctx: Context<CloseTraderAtaContext>) -> Result<()> {
         signer
     );
     token::close_account(cpi_ctx_close)?;
+    trader_state.position_count = trader_state.position_count.saturating_sub(1);
     
     msg!("Closed TraderState ATA for mint: {}. Rent returned to owner.", 
         ctx.accounts.trader_token_account.mint);
+
+    emit!(TraderPositionClosed {
+        trader_state: trader_state.key(),
+        mint: ctx.accounts.trader_position.mint,
+        position_count: trader_state.position_count,
+        swept,
+    });
+
+    Ok(())
+}
+
+/// Check that `accounts` holds every TraderPosition of this TraderState, each
+/// followed by its ATA, and that every ATA is empty or already closed.
+/// Remaining accounts layout: [position_0, ata_0, position_1, ata_1, ...].
+fn verify_non_base_atas_empty(trader_state: &Account<TraderState>, accounts: &[AccountInfo]) -> Result<()> {
+    require!(
+        accounts.len() == trader_state.position_count as usize * 2,
+        ErrorCode::SettlementAccountsMismatch
+    );
+
+    let mut seen: Vec<Pubkey> = Vec::with_capacity(trader_state.position_count as usize);
+    for pair in accounts.chunks(2) {
+        let (position_info, ata_info) = (&pair[0], &pair[1]);
+
+        require!(*position_info.owner == crate::ID, ErrorCode::SettlementAccountsMismatch);
+        let position = TraderPosition::try_deserialize(&mut &position_info.try_borrow_data()?[..])?;
+        require!(position.trader_state == trader_state.key(), ErrorCode::SettlementAccountsMismatch);
+        require!(!seen.contains(position_info.key), ErrorCode::SettlementAccountsMismatch);
+        seen.push(position_info.key());
+
+        require!(
+            ata_info.key() == get_associated_token_address(&trader_state.key(), &position.mint),
+            ErrorCode::SettlementAccountsMismatch
+        );
+        // A closed ATA cannot hold anything
+        if ata_info.data_is_empty() {
+            continue;
+        }
+        require!(*ata_info.owner == token::ID, ErrorCode::SettlementAccountsMismatch);
+        let ata = TokenAccount::try_deserialize(&mut &ata_info.try_borrow_data()?[..])?;
+        require!(ata.amount == 0, ErrorCode::StrandedAssets);
+    }
     Ok(())
 }
 
 /// settlement: Validate that TraderState holds only Base Asset and amount >= current_value.
-/// Locks the state as 'Settled' to enable withdrawal.
+/// Every non-base ATA must be passed (with its TraderPosition) as remaining accounts
+/// and be empty. Charges the performance fee on equity above the high-water mark.
+/// Moves a Paused or Settling TraderState to Settled to enable withdrawal.
 pub fn settle_trader_state(ctx: Context<SettleTraderState>) -> Result<()> {
     let trader_state = &mut ctx.accounts.trader_state;
     let trader_token_account = &ctx.accounts.trader_token_account;
+    let global_config = &ctx.accounts.global_config;
 
-    require!(trader_state.is_paused, ErrorCode::TraderNotPaused);
+    require!(
+        trader_state.status.can_transition_to(TraderStatus::Settled),
+        trader_state.status.transition_error(TraderStatus::Settled)
+    );
     require!(trader_token_account.mint == ctx.accounts.vault.base_mint, ErrorCode::MintMismatch);
+    require!(trader_state.open_positions == 0, ErrorCode::PositionsOpen);
+    verify_non_base_atas_empty(trader_state, ctx.remaining_accounts)?;
     
     // Ensure solvency/full settlement
     // We require that the Base Asset holdings are at least the tracked equity.
     // This implicitly checks that we aren't hiding funds in other assets (if we assume strict accounting).
     require!(trader_token_account.amount >= trader_state.current_value, ErrorCode::InsufficientFunds);
 
-    trader_state.is_settled = true;
+    // Performance Fee (High-Water Mark)
+    // Fee destination is the admin's Base Asset token account.
+    require!(ctx.accounts.performance_fee_account.owner == global_config.admin, ErrorCode::InvalidFeeDestination);
+    require!(ctx.accounts.performance_fee_account.mint == ctx.accounts.vault.base_mint, ErrorCode::InvalidFeeDestination);
+
+    let fee = trader_state.crystallize_performance_fee(
+        trader_token_account.amount,
+        global_config.performance_fee_bps,
+    )?;
+
+    if fee > 0 {
+        let allocation_seed = trader_state.allocation_seed();
+        let seeds = &[
+            b"trader_state",
+            trader_state.owner.as_ref(),
+            trader_state.trader.as_ref(),
+            allocation_seed.as_slice(),
+            &[trader_state.bump],
+        ];
+        let signer = &[&seeds[..]];
+
+        let cpi_accounts = Transfer {
+            from: ctx.accounts.trader_token_account.to_account_info(),
+            to: ctx.accounts.performance_fee_account.to_account_info(),
+            authority: trader_state.to_account_info(),
+        };
+        let cpi_ctx = CpiContext::new_with_signer(
+            ctx.accounts.token_program.to_account_info(),
+            cpi_accounts,
+            signer
+        );
+        token::transfer(cpi_ctx, fee)?;
+        msg!("Paid performance fee: {}. New HWM: {}", fee, trader_state.high_water_mark);
+    }
+
+    trader_state.last_crystallized_at = Clock::get()?.unix_timestamp;
+    trader_state.transition(TraderStatus::Settled)?;
     msg!("TraderState settled. Equity: {}", trader_state.current_value);
+
+    let settled_event = TraderStateSettled {
+        trader_state: trader_state.key(),
+        equity: trader_state.current_value,
+        performance_fee: fee,
+        high_water_mark: trader_state.high_water_mark,
+        cumulative_profit: trader_state.cumulative_profit,
+    };
+    emit_event!(ctx, settled_event);
+
+    Ok(())
+}
+
+/// Periodic performance fee crystallization without exiting the allocation.
+/// Backend authority only. Requires all funds to be held in Base Asset.
+/// Enforces MIN_CRYSTALLIZATION_INTERVAL between crystallizations.
+pub fn crystallize_performance_fee(ctx: Context<CrystallizePerformanceFee>) -> Result<()> {
+    let trader_state = &mut ctx.accounts.trader_state;
+    let trader_token_account = &ctx.accounts.trader_token_account;
+    let global_config = &ctx.accounts.global_config;
+    let now = Clock::get()?.unix_timestamp;
+
+    require!(!global_config.trading_halted, ErrorCode::TradingHalted);
+    require!(!ctx.accounts.vault.is_paused, ErrorCode::Paused);
+    // Sync-phase value changes are not trading performance
+    if trader_state.status != TraderStatus::Created {
+        trader_state.status.require_active()?;
+    }
+    require!(
+        now >= trader_state.last_crystallized_at.saturating_add(MIN_CRYSTALLIZATION_INTERVAL),
+        ErrorCode::CrystallizationTooSoon
+    );
+
+    // Equity is only measurable while everything sits in Base Asset.
+    require!(trader_state.open_positions == 0, ErrorCode::PositionsOpen);
+    require!(trader_token_account.amount >= trader_state.current_value, ErrorCode::InsufficientFunds);
+
+    require!(ctx.accounts.performance_fee_account.owner == global_config.admin, ErrorCode::InvalidFeeDestination);
+    require!(ctx.accounts.performance_fee_account.mint == ctx.accounts.vault.base_mint, ErrorCode::InvalidFeeDestination);
+
+    let fee = trader_state.crystallize_performance_fee(
+        trader_token_account.amount,
+        global_config.performance_fee_bps,
+    )?;
+
+    if fee > 0 {
+        let allocation_seed = trader_state.allocation_seed();
+        let seeds = &[
+            b"trader_state",
+            trader_state.owner.as_ref(),
+            trader_state.trader.as_ref(),
+            allocation_seed.as_slice(),
+            &[trader_state.bump],
+        ];
+        let signer = &[&seeds[..]];
+
+        let cpi_accounts = Transfer {
+            from: ctx.accounts.trader_token_account.to_account_info(),
+            to: ctx.accounts.performance_fee_account.to_account_info(),
+            authority: trader_state.to_account_info(),
+        };
+        let cpi_ctx = CpiContext::new_with_signer(
+            ctx.accounts.token_program.to_account_info(),
+            cpi_accounts,
+            signer
+        );
+        token::transfer(cpi_ctx, fee)?;
+    }
+
+    trader_state.last_crystallized_at = now;
+    msg!(
+        "Crystallized performance fee: {}. HWM: {}. Cumulative profit: {}",
+        fee,
+        trader_state.high_water_mark,
+        trader_state.cumulative_profit
+    );
+
+    emit!(PerformanceFeeCrystallized {
+        trader_state: trader_state.key(),
+        fee,
+        high_water_mark: trader_state.high_water_mark,
+        cumulative_profit: trader_state.cumulative_profit,
+    });
+
     Ok(())
 }
 
 /// withdraw: Exit flow.
-/// Prerequisites: Paused && Settled.
+/// Prerequisites: TraderStatus::Settled, with every non-base ATA closed.
 /// Flow: TraderState -> UserVault -> User Wallet.
 /// Closes TraderState and its ATA.
 pub fn withdraw_trader_state(ctx: Context<WithdrawTraderState>) -> Result<()> {
-    let trader_state = &ctx.accounts.trader_state;
+    let trader_state = &mut ctx.accounts.trader_state;
     let vault = &ctx.accounts.vault;
     
-    require!(trader_state.is_paused, ErrorCode::TraderNotPaused);
-    require!(trader_state.is_settled, ErrorCode::NotSettled);
+    require!(trader_state.status == TraderStatus::Settled, ErrorCode::NotSettled);
+    trader_state.transition(TraderStatus::Closed)?;
+    require!(trader_state.open_positions == 0, ErrorCode::PositionsOpen);
+    // Non-base ATAs would be orphaned (and could strand tokens) once the PDA closes
+    require!(trader_state.position_count == 0, ErrorCode::PositionAccountsRemain);
 
     // 1. Transfer TraderState -> UserVault
+    let allocation_seed = trader_state.allocation_seed();
     let trader_seeds = &[
         b"trader_state",
         trader_state.owner.as_ref(),
         trader_state.trader.as_ref(),
+        allocation_seed.as_slice(),
         &[trader_state.bump],
     ];
     let trader_signer = &[&trader_seeds[..]];
@@ -256,11 +738,342 @@ pub fn withdraw_trader_state(ctx: Context<WithdrawTraderState>) -> Result<()> {
     token::close_account(close_ctx)?;
 
     msg!("Withdrawal complete. Amount: {}. TraderState closed.", amount);
+
+    emit!(TraderStateWithdrawn {
+        trader_state: trader_state.key(),
+        owner: trader_state.owner,
+        amount,
+        current_value: 0,
+        closed: true,
+    });
+
     // TraderState Account itself is closed via `close = owner` in struct
     Ok(())
 }
 
+/// Partial withdrawal: take profits while keeping the allocation alive.
+/// Owner-only. Requires the Base Asset ATA to hold `amount` and no open
+/// positions, so current_value is all Base Asset.
+/// Flow: TraderState -> UserVault -> User Wallet.
+/// The performance fee on the withdrawn fraction of gains above the HWM is
+/// taken out of `amount`; current_value and high_water_mark then shrink
+/// proportionally.
+pub fn partial_withdraw_trader_state(ctx: Context<PartialWithdrawTraderState>, amount: u64) -> Result<()> {
+    let trader_state = &mut ctx.accounts.trader_state;
+    let vault = &ctx.accounts.vault;
+    let global_config = &ctx.accounts.global_config;
+
+    // Sync and settlement swaps are still moving value around, and pausing
+    // only holds them: a paused TraderState resumes where it left off.
+    let phase = match trader_state.status {
+        TraderStatus::Paused => trader_state.resume_status,
+        status => status,
+    };
+    require!(phase != TraderStatus::Syncing, ErrorCode::AlreadySyncing);
+    require!(phase != TraderStatus::Settling, ErrorCode::TraderSettling);
+    // Positions are valued at cost; the fee and HWM math needs realized value
+    require!(trader_state.open_positions == 0, ErrorCode::PositionsOpen);
+    require!(ctx.accounts.trader_token_account.amount >= amount, ErrorCode::InsufficientFunds);
+
+    require!(ctx.accounts.performance_fee_account.owner == global_config.admin, ErrorCode::InvalidFeeDestination);
+    require!(ctx.accounts.performance_fee_account.mint == vault.base_mint, ErrorCode::InvalidFeeDestination);
+
+    let fee = trader_state.withdrawal_performance_fee(amount, global_config.performance_fee_bps)?;
+    trader_state.reduce_capital(amount)?;
+    let net_amount = amount - fee;
+
+    let allocation_seed = trader_state.allocation_seed();
+    let trader_seeds = &[
+        b"trader_state",
+        trader_state.owner.as_ref(),
+        trader_state.trader.as_ref(),
+        allocation_seed.as_slice(),
+        &[trader_state.bump],
+    ];
+    let trader_signer = &[&trader_seeds[..]];
+
+    // 1. Transfer the performance fee to the admin
+    if fee > 0 {
+        let cpi_accounts_fee = Transfer {
+            from: ctx.accounts.trader_token_account.to_account_info(),
+            to: ctx.accounts.performance_fee_account.to_account_info(),
+            authority: trader_state.to_account_info(),
+        };
+        let cpi_ctx_fee = CpiContext::new_with_signer(
+            ctx.accounts.token_program.to_account_info(),
+            cpi_accounts_fee,
+            trader_signer
+        );
+        token::transfer(cpi_ctx_fee, fee)?;
+    }
+
+    // 2. Transfer TraderState -> UserVault
+    let cpi_accounts_trader = Transfer {
+        from: ctx.accounts.trader_token_account.to_account_info(),
+        to: ctx.accounts.vault_token_account.to_account_info(),
+        authority: trader_state.to_account_info(),
+    };
+    let cpi_ctx_trader = CpiContext::new_with_signer(
+        ctx.accounts.token_program.to_account_info(),
+        cpi_accounts_trader,
+        trader_signer
+    );
+    token::transfer(cpi_ctx_trader, net_amount)?;
+
+    // 3. Transfer UserVault -> Owner Wallet
+    let vault_seeds = &[
+        b"user_vault_v1",
+        vault.owner.as_ref(),
+        &[vault.bump],
+    ];
+    let vault_signer = &[&vault_seeds[..]];
+
+    let cpi_accounts_vault = Transfer {
+        from: ctx.accounts.vault_token_account.to_account_info(),
+        to: ctx.accounts.owner_token_account.to_account_info(),
+        authority: vault.to_account_info(),
+    };
+    let cpi_ctx_vault = CpiContext::new_with_signer(
+        ctx.accounts.token_program.to_account_info(),
+        cpi_accounts_vault,
+        vault_signer
+    );
+    token::transfer(cpi_ctx_vault, net_amount)?;
+
+    msg!(
+        "Partial withdrawal complete. Amount: {}. Performance fee: {}. Remaining value: {}",
+        amount,
+        fee,
+        trader_state.current_value
+    );
+
+    if fee > 0 {
+        emit!(PerformanceFeeCrystallized {
+            trader_state: trader_state.key(),
+            fee,
+            high_water_mark: trader_state.high_water_mark,
+            cumulative_profit: trader_state.cumulative_profit,
+        });
+    }
+
+    emit!(TraderStateWithdrawn {
+        trader_state: trader_state.key(),
+        owner: trader_state.owner,
+        amount,
+        current_value: trader_state.current_value,
+        closed: false,
+    });
+
+    Ok(())
+}
+
+/// Migrate a TraderState created under the original account layout.
+/// Owner-only; owner pays the extra rent. The legacy flags are folded into a
+/// TraderStatus and new fields are zero-initialized, so the account becomes
+/// allocation 0, whose PDA is the legacy address.
+/// Legacy ATAs had no TraderPosition: every non-base ATA must be passed with
+/// its (uncreated) TraderPosition PDA so it is registered.
+/// Remaining accounts layout: [position_0, ata_0, position_1, ata_1, ...].
+///
+/// The legacy current_value only moved on swaps into the Base Asset, so the
+/// part of it not held in Base Asset is what the open positions cost. That
+/// amount is split evenly across them as cost basis, keeping
+/// current_value = base holdings + cost basis of open positions. With no open
+/// positions it is written off. Base Asset beyond the legacy current_value is
+/// booked as profit first, so current_value never starts below the Base balance.
+pub fn migrate_trader_state<'info>(ctx: Context<'_, '_, 'info, 'info, MigrateTraderState<'info>>) -> Result<()> {
+    let info = ctx.accounts.trader_state.to_account_info();
+    let owner = ctx.accounts.owner.key();
+    require!(info.data_len() == TraderState::LEGACY_SPACE, ErrorCode::AlreadyMigrated);
+    require!(ctx.remaining_accounts.len() % 2 == 0, ErrorCode::MigrationAccountsMismatch);
+
+    // Legacy layout: discriminator | owner | trader | vault | bump | current_value
+    // | high_water_mark | cumulative_profit | is_paused | is_settled | is_initialized
+    let mut migrated = {
+        let data = info.try_borrow_data()?;
+        require!(
+            data[..8] == *TraderState::DISCRIMINATOR,
+            anchor_lang::error::ErrorCode::AccountDiscriminatorMismatch
+        );
+        let pubkey_at = |at: usize| Pubkey::try_from(&data[at..at + 32]).unwrap();
+        let u64_at = |at: usize| u64::from_le_bytes(data[at..at + 8].try_into().unwrap());
+        require!(pubkey_at(8) == owner, ErrorCode::Unauthorized);
+
+        let (is_paused, is_settled, is_initialized) = (data[129] != 0, data[130] != 0, data[131] != 0);
+        let resume_status = if is_initialized { TraderStatus::Active } else { TraderStatus::Created };
+        let status = if is_settled {
+            TraderStatus::Settled
+        } else if is_paused {
+            TraderStatus::Paused
+        } else {
+            resume_status
+        };
+        let current_value = u64_at(105);
+
+        TraderState {
+            owner,
+            trader: pubkey_at(40),
+            vault: pubkey_at(72),
+            bump: data[104],
+            current_value,
+            high_water_mark: u64_at(113),
+            cumulative_profit: u64_at(121) as i64,
+            status,
+            resume_status,
+            last_crystallized_at: Clock::get()?.unix_timestamp,
+            peak_value: current_value,
+            ..Default::default()
+        }
+    };
+
+    let expected = Pubkey::create_program_address(
+        &[b"trader_state", owner.as_ref(), migrated.trader.as_ref(), &[migrated.bump]],
+        ctx.program_id,
+    ).map_err(|_| ErrorCode::Unauthorized)?;
+    require_keys_eq!(expected, info.key(), ErrorCode::Unauthorized);
+    require_keys_eq!(migrated.vault, ctx.accounts.vault.key(), ErrorCode::Unauthorized);
+
+    let trader_state_key = info.key();
+    let mut holdings: Vec<(&AccountInfo<'info>, Pubkey, u8, u64)> = Vec::with_capacity(ctx.remaining_accounts.len() / 2);
+    for pair in ctx.remaining_accounts.chunks(2) {
+        let (position_info, ata_info) = (&pair[0], &pair[1]);
+
+        require!(*ata_info.owner == token::ID, ErrorCode::MigrationAccountsMismatch);
+        let ata = TokenAccount::try_deserialize(&mut &ata_info.try_borrow_data()?[..])?;
+        require_keys_neq!(ata.mint, ctx.accounts.vault.base_mint, ErrorCode::BaseMintPosition);
+        require!(
+            ata_info.key() == get_associated_token_address(&trader_state_key, &ata.mint),
+            ErrorCode::MigrationAccountsMismatch
+        );
+
+        let (position_key, position_bump) = Pubkey::find_program_address(
+            &[b"trader_position", trader_state_key.as_ref(), ata.mint.as_ref()],
+            ctx.program_id,
+        );
+        // An existing (or repeated) position means the pair was passed twice
+        require!(
+            position_info.key() == position_key
+                && position_info.data_is_empty()
+                && !holdings.iter().any(|(info, ..)| info.key == position_info.key),
+            ErrorCode::MigrationAccountsMismatch
+        );
+        holdings.push((position_info, ata.mint, position_bump, ata.amount));
+    }
+
+    // Base Asset the legacy current_value does not count was gained outside it
+    let base_balance = ctx.accounts.trader_token_account.amount;
+    if migrated.current_value < base_balance {
+        let missed = base_balance - migrated.current_value;
+        migrated.current_value = base_balance;
+        migrated.peak_value = base_balance;
+        migrated.cumulative_profit = migrated
+            .cumulative_profit
+            .checked_add(i64::try_from(missed).map_err(|_| ErrorCode::MathOverflow)?)
+            .ok_or(ErrorCode::MathOverflow)?;
+    }
+
+    // Capital the legacy current_value counts but the Base Asset ATA does not hold
+    let unbooked = migrated.current_value - base_balance;
+    let open_count = holdings.iter().filter(|(.., quantity)| *quantity > 0).count() as u64;
+    if open_count == 0 {
+        migrated.current_value -= unbooked;
+        migrated.peak_value = migrated.current_value;
+        migrated.cumulative_profit = migrated
+            .cumulative_profit
+            .checked_sub(i64::try_from(unbooked).map_err(|_| ErrorCode::MathOverflow)?)
+            .ok_or(ErrorCode::MathOverflow)?;
+    }
+    let (cost_share, mut cost_remainder) = match open_count {
+        0 => (0, 0),
+        n => (unbooked / n, unbooked % n),
+    };
+
+    for (position_info, mint, position_bump, quantity) in holdings {
+        let position_seeds: &[&[u8]] = &[
+            b"trader_position",
+            trader_state_key.as_ref(),
+            mint.as_ref(),
+            &[position_bump],
+        ];
+        let cpi_accounts = system_program::CreateAccount {
+            from: ctx.accounts.owner.to_account_info(),
+            to: position_info.clone(),
+        };
+        let signer = &[position_seeds];
+        let cpi_ctx = CpiContext::new_with_signer(ctx.accounts.system_program.to_account_info(), cpi_accounts, signer);
+        system_program::create_account(
+            cpi_ctx,
+            Rent::get()?.minimum_balance(TraderPosition::INIT_SPACE),
+            TraderPosition::INIT_SPACE as u64,
+            ctx.program_id,
+        )?;
+
+        let cost_basis = if quantity > 0 {
+            cost_share + std::mem::take(&mut cost_remainder)
+        } else {
+            0
+        };
+        let position = TraderPosition {
+            trader_state: trader_state_key,
+            mint,
+            bump: position_bump,
+            quantity,
+            cost_basis,
+        };
+        position.try_serialize(&mut &mut position_info.try_borrow_mut_data()?[..])?;
+
+        migrated.position_count = migrated.position_count.checked_add(1).ok_or(ErrorCode::MathOverflow)?;
+        if position.is_open() {
+            migrated.open_positions = migrated.open_positions.checked_add(1).ok_or(ErrorCode::MathOverflow)?;
+        }
+    }
+
+    let rent_due = Rent::get()?
+        .minimum_balance(TraderState::INIT_SPACE)
+        .saturating_sub(info.lamports());
+    if rent_due > 0 {
+        let cpi_accounts = system_program::Transfer {
+            from: ctx.accounts.owner.to_account_info(),
+            to: info.clone(),
+        };
+        let cpi_ctx = CpiContext::new(ctx.accounts.system_program.to_account_info(), cpi_accounts);
+        system_program::transfer(cpi_ctx, rent_due)?;
+    }
+    info.resize(TraderState::INIT_SPACE)?;
+    migrated.try_serialize(&mut &mut info.try_borrow_mut_data()?[..])?;
+
+    msg!("Migrated TraderState {} to current layout.", info.key());
+
+    emit!(TraderStateMigrated {
+        trader_state: info.key(),
+        owner,
+        status: migrated.status,
+        position_count: migrated.position_count,
+    });
+
+    Ok(())
+}
+
+/// Create the TradeJournal of a TraderState created before journals existed
+/// (e.g. after migrate_trader_state). Permissionless: the address is fixed by
+/// the TraderState and the journal starts empty. Payer funds the rent.
+/// Swaps, close and withdraw all require the journal.
+pub fn init_trade_journal(ctx: Context<InitTradeJournal>) -> Result<()> {
+    let mut journal = ctx.accounts.trade_journal.load_init()?;
+    journal.trader_state = ctx.accounts.trader_state.key();
+    journal.bump = ctx.bumps.trade_journal;
+    msg!("Initialized TradeJournal for TraderState: {}", journal.trader_state);
+
+    emit!(TradeJournalInitialized {
+        trade_journal: ctx.accounts.trade_journal.key(),
+        trader_state: journal.trader_state,
+    });
+
+    Ok(())
+}
+
 #[derive(Accounts)]
+#[instruction(amount: u64, allocation_id: u16)]
 pub struct CreateTraderState<'info> {
     #[account(mut)]
     pub owner: Signer<'info>,
@@ -279,10 +1092,24 @@ pub struct CreateTraderState<'info> {
         init,
         payer = owner,
         space = TraderState::INIT_SPACE,
-        seeds = [b"trader_state", owner.key().as_ref(), trader.key().as_ref()],
+        seeds = [
+            b"trader_state",
+            owner.key().as_ref(),
+            trader.key().as_ref(),
+            TraderState::seed_for_allocation(allocation_id).as_slice()
+        ],
         bump
     )]
     pub trader_state: Account<'info, TraderState>,
+
+    #[account(
+        init,
+        payer = owner,
+        space = TradeJournal::INIT_SPACE,
+        seeds = [b"trade_journal", trader_state.key().as_ref()],
+        bump
+    )]
+    pub trade_journal: AccountLoader<'info, TradeJournal>,
     
     #[account(
         mut,
@@ -301,12 +1128,112 @@ pub struct CreateTraderState<'info> {
     
     #[account(address = vault.base_mint)]
     pub mint: Account<'info, Mint>,
+
+    #[account(
+        seeds = [b"global_config"],
+        bump,
+    )]
+    pub global_config: Account<'info, GlobalConfig>,
     
     pub system_program: Program<'info, System>,
     pub token_program: Program<'info, Token>,
     pub associated_token_program: Program<'info, AssociatedToken>,
 }
 
+#[derive(Accounts)]
+pub struct FundTraderState<'info> {
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    #[account(
+        has_one = owner @ ErrorCode::Unauthorized,
+        seeds = [b"user_vault_v1", owner.key().as_ref()],
+        bump = vault.bump
+    )]
+    pub vault: Account<'info, UserVault>,
+
+    #[account(
+        mut,
+        has_one = owner @ ErrorCode::Unauthorized,
+        has_one = vault @ ErrorCode::Unauthorized,
+        seeds = [
+            b"trader_state",
+            owner.key().as_ref(),
+            trader_state.trader.as_ref(),
+            trader_state.allocation_seed().as_slice()
+        ],
+        bump = trader_state.bump
+    )]
+    pub trader_state: Account<'info, TraderState>,
+
+    #[account(
+        mut,
+        associated_token::mint = vault.base_mint,
+        associated_token::authority = vault
+    )]
+    pub vault_token_account: Account<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        associated_token::mint = vault.base_mint,
+        associated_token::authority = trader_state
+    )]
+    pub trader_token_account: Account<'info, TokenAccount>,
+
+    #[account(
+        seeds = [b"global_config"],
+        bump,
+    )]
+    pub global_config: Account<'info, GlobalConfig>,
+
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct MigrateTraderState<'info> {
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    /// CHECK: Legacy layout cannot be deserialized as TraderState.
+    /// Discriminator, owner and PDA address are validated in the instruction.
+    #[account(mut, owner = crate::ID)]
+    pub trader_state: UncheckedAccount<'info>,
+
+    #[account(
+        seeds = [b"user_vault_v1", owner.key().as_ref()],
+        bump = vault.bump
+    )]
+    pub vault: Account<'info, UserVault>,
+
+    /// Base Asset ATA; its balance splits current_value into base and position cost.
+    #[account(
+        associated_token::mint = vault.base_mint,
+        associated_token::authority = trader_state
+    )]
+    pub trader_token_account: Account<'info, TokenAccount>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct InitTradeJournal<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    pub trader_state: Account<'info, TraderState>,
+
+    #[account(
+        init,
+        payer = payer,
+        space = TradeJournal::INIT_SPACE,
+        seeds = [b"trade_journal", trader_state.key().as_ref()],
+        bump
+    )]
+    pub trade_journal: AccountLoader<'info, TradeJournal>,
+
+    pub system_program: Program<'info, System>,
+}
+
 #[derive(Accounts)]
 pub struct UpdateTraderState<'info> {
     #[account(mut)]
@@ -331,6 +1258,14 @@ pub struct CloseTraderState<'info> {
         has_one = vault @ ErrorCode::Unauthorized
     )]
     pub trader_state: Account<'info, TraderState>,
+
+    #[account(
+        mut,
+        close = owner,
+        seeds = [b"trade_journal", trader_state.key().as_ref()],
+        bump = trade_journal.load()?.bump
+    )]
+    pub trade_journal: AccountLoader<'info, TradeJournal>,
     
     #[account(
         seeds = [b"user_vault_v1", owner.key().as_ref()],
@@ -355,6 +1290,7 @@ pub struct CloseTraderState<'info> {
     pub token_program: Program<'info, Token>,
 }
 
+#[cfg_attr(feature = "event-cpi", event_cpi)]
 #[derive(Accounts)]
 pub struct SettleTraderState<'info> {
     #[account(mut)]
@@ -370,7 +1306,12 @@ pub struct SettleTraderState<'info> {
         mut,
         has_one = owner @ ErrorCode::Unauthorized,
         has_one = vault @ ErrorCode::Unauthorized,
-        seeds = [b"trader_state", owner.key().as_ref(), trader_state.trader.as_ref()],
+        seeds = [
+            b"trader_state",
+            owner.key().as_ref(),
+            trader_state.trader.as_ref(),
+            trader_state.allocation_seed().as_slice()
+        ],
         bump = trader_state.bump
     )]
     pub trader_state: Account<'info, TraderState>,
@@ -378,10 +1319,67 @@ pub struct SettleTraderState<'info> {
     // Explicit Token Account for Validation
     // Must be holding Base Asset (vault.base_mint)
     #[account(
+        mut,
+        associated_token::mint = vault.base_mint,
+        associated_token::authority = trader_state
+    )]
+    pub trader_token_account: Account<'info, TokenAccount>,
+
+    // Performance fee destination (admin's Base Asset account)
+    #[account(mut)]
+    pub performance_fee_account: Account<'info, TokenAccount>,
+
+    #[account(
+        seeds = [b"global_config"],
+        bump,
+    )]
+    pub global_config: Account<'info, GlobalConfig>,
+
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct CrystallizePerformanceFee<'info> {
+    pub authority: Signer<'info>, // Backend agent
+
+    #[account(
+        seeds = [b"user_vault_v1", trader_state.owner.as_ref()],
+        bump = vault.bump,
+        has_one = authority @ ErrorCode::Unauthorized,
+    )]
+    pub vault: Account<'info, UserVault>,
+
+    #[account(
+        mut,
+        has_one = vault @ ErrorCode::Unauthorized,
+        seeds = [
+            b"trader_state",
+            trader_state.owner.as_ref(),
+            trader_state.trader.as_ref(),
+            trader_state.allocation_seed().as_slice()
+        ],
+        bump = trader_state.bump
+    )]
+    pub trader_state: Account<'info, TraderState>,
+
+    #[account(
+        mut,
         associated_token::mint = vault.base_mint,
         associated_token::authority = trader_state
     )]
     pub trader_token_account: Account<'info, TokenAccount>,
+
+    // Performance fee destination (admin's Base Asset account)
+    #[account(mut)]
+    pub performance_fee_account: Account<'info, TokenAccount>,
+
+    #[account(
+        seeds = [b"global_config"],
+        bump,
+    )]
+    pub global_config: Account<'info, GlobalConfig>,
+
+    pub token_program: Program<'info, Token>,
 }
 
 #[derive(Accounts)]
@@ -400,10 +1398,23 @@ pub struct WithdrawTraderState<'info> {
         close = owner,
         has_one = owner @ ErrorCode::Unauthorized,
         has_one = vault @ ErrorCode::Unauthorized,
-        seeds = [b"trader_state", owner.key().as_ref(), trader_state.trader.as_ref()],
+        seeds = [
+            b"trader_state",
+            owner.key().as_ref(),
+            trader_state.trader.as_ref(),
+            trader_state.allocation_seed().as_slice()
+        ],
         bump = trader_state.bump
     )]
     pub trader_state: Account<'info, TraderState>,
+
+    #[account(
+        mut,
+        close = owner,
+        seeds = [b"trade_journal", trader_state.key().as_ref()],
+        bump = trade_journal.load()?.bump
+    )]
+    pub trade_journal: AccountLoader<'info, TradeJournal>,
     
     // Source: TraderState ATA
     #[account(
@@ -432,6 +1443,68 @@ pub struct WithdrawTraderState<'info> {
     pub token_program: Program<'info, Token>,
 }
 
+#[derive(Accounts)]
+pub struct PartialWithdrawTraderState<'info> {
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    #[account(
+        seeds = [b"user_vault_v1", owner.key().as_ref()],
+        bump = vault.bump
+    )]
+    pub vault: Account<'info, UserVault>,
+
+    #[account(
+        mut,
+        has_one = owner @ ErrorCode::Unauthorized,
+        has_one = vault @ ErrorCode::Unauthorized,
+        seeds = [
+            b"trader_state",
+            owner.key().as_ref(),
+            trader_state.trader.as_ref(),
+            trader_state.allocation_seed().as_slice()
+        ],
+        bump = trader_state.bump
+    )]
+    pub trader_state: Account<'info, TraderState>,
+
+    // Source: TraderState ATA
+    #[account(
+        mut,
+        associated_token::mint = vault.base_mint,
+        associated_token::authority = trader_state
+    )]
+    pub trader_token_account: Account<'info, TokenAccount>,
+
+    // Transit: UserVault ATA
+    #[account(
+        mut,
+        associated_token::mint = vault.base_mint,
+        associated_token::authority = vault
+    )]
+    pub vault_token_account: Account<'info, TokenAccount>,
+
+    // Destination: Owner Wallet ATA
+    #[account(
+        mut,
+        associated_token::mint = vault.base_mint,
+        associated_token::authority = owner
+    )]
+    pub owner_token_account: Account<'info, TokenAccount>,
+
+    // Performance fee destination (admin's Base Asset account)
+    #[account(mut)]
+    pub performance_fee_account: Account<'info, TokenAccount>,
+
+    #[account(
+        seeds = [b"global_config"],
+        bump,
+    )]
+    pub global_config: Account<'info, GlobalConfig>,
+
+    pub token_program: Program<'info, Token>,
+}
+
 // =========================================================================
 // PHASE 7: Multi-Asset Support Account Contexts
 // =========================================================================
@@ -441,10 +1514,23 @@ pub struct WithdrawTraderState<'info> {
 pub struct CreateTraderAta<'info> {
     #[account(mut)]
     pub owner: Signer<'info>,
+
+    #[account(
+        seeds = [b"user_vault_v1", owner.key().as_ref()],
+        bump = vault.bump
+    )]
+    pub vault: Account<'info, UserVault>,
     
     #[account(
+        mut,
         has_one = owner @ ErrorCode::Unauthorized,
-        seeds = [b"trader_state", owner.key().as_ref(), trader_state.trader.as_ref()],
+        has_one = vault @ ErrorCode::Unauthorized,
+        seeds = [
+            b"trader_state",
+            owner.key().as_ref(),
+            trader_state.trader.as_ref(),
+            trader_state.allocation_seed().as_slice()
+        ],
         bump = trader_state.bump
     )]
     pub trader_state: Account<'info, TraderState>,
@@ -458,6 +1544,15 @@ pub struct CreateTraderAta<'info> {
         associated_token::authority = trader_state
     )]
     pub trader_token_account: Account<'info, TokenAccount>,
+
+    #[account(
+        init_if_needed,
+        payer = owner,
+        space = TraderPosition::INIT_SPACE,
+        seeds = [b"trader_position", trader_state.key().as_ref(), mint.key().as_ref()],
+        bump
+    )]
+    pub trader_position: Account<'info, TraderPosition>,
     
     pub token_program: Program<'info, Token>,
     pub associated_token_program: Program<'info, AssociatedToken>,
@@ -480,33 +1575,100 @@ pub struct MarkTraderInitialized<'info> {
     #[account(
         mut,
         has_one = vault @ ErrorCode::Unauthorized,
-        seeds = [b"trader_state", trader_state.owner.as_ref(), trader_state.trader.as_ref()],
+        seeds = [
+            b"trader_state",
+            trader_state.owner.as_ref(),
+            trader_state.trader.as_ref(),
+            trader_state.allocation_seed().as_slice()
+        ],
         bump = trader_state.bump
     )]
     pub trader_state: Account<'info, TraderState>,
+
+    #[account(
+        seeds = [b"global_config"],
+        bump,
+    )]
+    pub global_config: Account<'info, GlobalConfig>,
 }
 
 
+/// Phase 7C: Start / finish portfolio sync.
+/// Backend authority only - user cannot start or finish sync.
+#[derive(Accounts)]
+pub struct TraderSync<'info> {
+    pub authority: Signer<'info>, // Backend agent
+
+    #[account(
+        seeds = [b"user_vault_v1", trader_state.owner.as_ref()],
+        bump = vault.bump,
+        has_one = authority @ ErrorCode::Unauthorized,
+    )]
+    pub vault: Account<'info, UserVault>,
+
+    #[account(
+        mut,
+        has_one = vault @ ErrorCode::Unauthorized,
+        seeds = [
+            b"trader_state",
+            trader_state.owner.as_ref(),
+            trader_state.trader.as_ref(),
+            trader_state.allocation_seed().as_slice()
+        ],
+        bump = trader_state.bump
+    )]
+    pub trader_state: Account<'info, TraderState>,
+
+    #[account(
+        seeds = [b"global_config"],
+        bump,
+    )]
+    pub global_config: Account<'info, GlobalConfig>,
+}
+
 /// Phase 7.1: Close a TraderState ATA to reclaim rent.
-/// Owner-only. Requires is_paused = true all checked in instruction.
+/// Owner-only. Requires Paused or Settled, checked in instruction.
 #[derive(Accounts)]
 pub struct CloseTraderAtaContext<'info> {
     #[account(mut)]
     pub owner: Signer<'info>,
     
     #[account(
+        mut,
         has_one = owner @ ErrorCode::Unauthorized,
-        seeds = [b"trader_state", owner.key().as_ref(), trader_state.trader.as_ref()],
+        seeds = [
+            b"trader_state",
+            owner.key().as_ref(),
+            trader_state.trader.as_ref(),
+            trader_state.allocation_seed().as_slice()
+        ],
         bump = trader_state.bump
     )]
     pub trader_state: Account<'info, TraderState>,
     
-    /// The token account to close. Must be owned by TraderState.
+    /// The ATA to close. Must be the TraderState's ATA for the position's mint.
     #[account(
         mut,
-        token::authority = trader_state
+        associated_token::mint = trader_position.mint,
+        associated_token::authority = trader_state
     )]
     pub trader_token_account: Account<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        close = owner,
+        seeds = [b"trader_position", trader_state.key().as_ref(), trader_token_account.mint.as_ref()],
+        bump = trader_position.bump
+    )]
+    pub trader_position: Account<'info, TraderPosition>,
+
+    /// Receives any balance left in the ATA. Required only when it is non-empty.
+    #[account(
+        mut,
+        token::mint = trader_position.mint,
+        token::authority = owner
+    )]
+    pub owner_token_account: Option<Account<'info, TokenAccount>>,
     
     pub token_program: Program<'info, Token>,
 }
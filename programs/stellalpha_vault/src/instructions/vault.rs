@@ -13,16 +13,63 @@ pub fn initialize_vault(ctx: Context<InitializeVault>, authority: Pubkey, base_m
     vault.base_mint = base_mint;
     vault.allowed_mints = Vec::new(); // Start empty
     msg!("Vault initialized for owner: {} with Base Asset: {}", vault.owner, base_mint);
+
+    emit!(VaultInitialized {
+        vault: vault.key(),
+        owner: vault.owner,
+        authority,
+        base_mint,
+    });
+
     Ok(())
 }
 
-pub fn toggle_pause(ctx: Context<TogglePause>) -> Result<()> {
+/// Pause the vault. Owner-only. Idempotent.
+/// Freezes all backend activity on every TraderState under this vault.
+/// Owner exit paths (pause/settle/withdraw/close) remain open.
+pub fn pause_vault(ctx: Context<SetVaultPause>) -> Result<()> {
     let vault = &mut ctx.accounts.vault;
-    vault.is_paused = !vault.is_paused;
-    msg!("Vault pause state toggled to: {}", vault.is_paused);
+    vault.is_paused = true;
+    msg!("Vault paused.");
+
+    emit!(VaultPauseUpdated {
+        vault: vault.key(),
+        paused: true,
+    });
+
+    Ok(())
+}
+
+/// Unpause the vault. Owner-only. Idempotent.
+pub fn unpause_vault(ctx: Context<SetVaultPause>) -> Result<()> {
+    let vault = &mut ctx.accounts.vault;
+    vault.is_paused = false;
+    msg!("Vault unpaused.");
+
+    emit!(VaultPauseUpdated {
+        vault: vault.key(),
+        paused: false,
+    });
+
     Ok(())
 }
 
+/// Rotate the backend authority allowed to execute swaps for this vault.
+/// Owner-only. Funds and TraderStates are untouched.
+pub fn set_vault_authority(ctx: Context<SetVaultAuthority>, new_authority: Pubkey) -> Result<()> {
+    let vault = &mut ctx.accounts.vault;
+    let old_authority = vault.authority;
+    vault.authority = new_authority;
+    msg!("Vault authority rotated from {} to {}", old_authority, new_authority);
+
+    emit!(VaultAuthorityUpdated {
+        vault: vault.key(),
+        old_authority,
+        new_authority,
+    });
+
+    Ok(())
+}
 
 pub fn deposit_token(ctx: Context<DepositToken>, amount: u64) -> Result<()> {
     let cpi_accounts = Transfer {
@@ -34,6 +81,13 @@ pub fn deposit_token(ctx: Context<DepositToken>, amount: u64) -> Result<()> {
     let cpi_ctx = CpiContext::new(cpi_program, cpi_accounts);
     token::transfer(cpi_ctx, amount)?;
     msg!("Deposited {} tokens to vault", amount);
+
+    emit!(VaultDeposited {
+        vault: ctx.accounts.vault.key(),
+        mint: ctx.accounts.vault_token_account.mint,
+        amount,
+    });
+
     Ok(())
 }
 
@@ -55,6 +109,13 @@ pub fn withdraw_token(ctx: Context<WithdrawToken>, amount: u64) -> Result<()> {
     let cpi_ctx = CpiContext::new_with_signer(cpi_program, cpi_accounts, signer);
     token::transfer(cpi_ctx, amount)?;
     msg!("Withdrew {} tokens from vault", amount);
+
+    emit!(VaultWithdrawn {
+        vault: vault.key(),
+        mint: ctx.accounts.vault_token_account.mint,
+        amount,
+    });
+
     Ok(())
 }
 
@@ -87,11 +148,25 @@ pub fn close_vault_ata(ctx: Context<CloseVaultAta>) -> Result<()> {
     token::close_account(cpi_ctx)?;
     
     msg!("Closed Vault ATA. Rent returned to owner.");
+
+    emit!(VaultAtaClosed {
+        vault: vault.key(),
+        mint: ctx.accounts.vault_token_account.mint,
+        token_account: ctx.accounts.vault_token_account.key(),
+    });
+
     Ok(())
 }
 
 pub fn init_vault_ata(ctx: Context<InitVaultAta>) -> Result<()> {
     msg!("Initialized Vault ATA for mint: {}", ctx.accounts.mint.key());
+
+    emit!(VaultAtaInitialized {
+        vault: ctx.accounts.vault.key(),
+        mint: ctx.accounts.mint.key(),
+        token_account: ctx.accounts.vault_token_account.key(),
+    });
+
     Ok(())
 }
 
@@ -134,7 +209,7 @@ pub struct CloseVaultAta<'info> {
 }
 
 #[derive(Accounts)]
-pub struct TogglePause<'info> {
+pub struct SetVaultPause<'info> {
     #[account(mut)]
     pub owner: Signer<'info>,
     
@@ -147,6 +222,18 @@ pub struct TogglePause<'info> {
     pub vault: Account<'info, UserVault>,
 }
 
+#[derive(Accounts)]
+pub struct SetVaultAuthority<'info> {
+    pub owner: Signer<'info>,
+
+    #[account(
+        mut,
+        has_one = owner @ ErrorCode::Unauthorized,
+        seeds = [b"user_vault_v1", owner.key().as_ref()],
+        bump = vault.bump
+    )]
+    pub vault: Account<'info, UserVault>,
+}
 
 #[derive(Accounts)]
 pub struct DepositToken<'info> {
@@ -1,6 +1,22 @@
 use anchor_lang::prelude::*;
 use instructions::*;
 
+/// Emit an accounting event from an instruction whose Accounts struct carries
+/// `#[cfg_attr(feature = "event-cpi", event_cpi)]`.
+/// With the `event-cpi` feature the event travels as self-CPI instruction data
+/// (cannot be lost to log truncation); otherwise it is logged with `emit!`.
+/// The event must not borrow from `ctx`.
+macro_rules! emit_event {
+    ($ctx:ident, $event:expr) => {{
+        #[cfg(feature = "event-cpi")]
+        {
+            let ctx = &$ctx;
+            emit_cpi!($event);
+        }
+        #[cfg(not(feature = "event-cpi"))]
+        emit!($event);
+    }};
+}
 
 // Re-export modules for convenience or testing access if needed
 pub mod state;
@@ -22,11 +38,17 @@ pub mod stellalpha_vault {
         instructions::vault::initialize_vault(ctx, authority, base_mint)
     }
 
-    pub fn initialize_global_config(ctx: Context<InitializeGlobalConfig>) -> Result<()> {
-        instructions::admin::initialize_global_config(ctx)
+    /// Create the GlobalConfig. The signer becomes admin; `guardian` may halt trading.
+    pub fn initialize_global_config(ctx: Context<InitializeGlobalConfig>, guardian: Pubkey) -> Result<()> {
+        instructions::admin::initialize_global_config(ctx, guardian)
+    }
+
+    /// Migrate the GlobalConfig from the original account layout. Admin only.
+    pub fn migrate_global_config(ctx: Context<MigrateGlobalConfig>) -> Result<()> {
+        instructions::admin::migrate_global_config(ctx)
     }
 
-    pub fn add_allowed_mint(ctx: Context<ManageWhitelist>, mint: Pubkey) -> Result<()> {
+    pub fn add_allowed_mint(ctx: Context<AddAllowedMint>, mint: Pubkey) -> Result<()> {
         instructions::admin::add_allowed_mint(ctx, mint)
     }
 
@@ -34,8 +56,19 @@ pub mod stellalpha_vault {
         instructions::admin::remove_allowed_mint(ctx, mint)
     }
 
-    pub fn toggle_pause(ctx: Context<TogglePause>) -> Result<()> {
-        instructions::vault::toggle_pause(ctx)
+    /// Pause the vault and every TraderState under it. Owner only.
+    pub fn pause_vault(ctx: Context<SetVaultPause>) -> Result<()> {
+        instructions::vault::pause_vault(ctx)
+    }
+
+    /// Unpause the vault. Owner only.
+    pub fn unpause_vault(ctx: Context<SetVaultPause>) -> Result<()> {
+        instructions::vault::unpause_vault(ctx)
+    }
+
+    /// Rotate the backend authority for this vault. Owner only.
+    pub fn set_vault_authority(ctx: Context<SetVaultAuthority>, new_authority: Pubkey) -> Result<()> {
+        instructions::vault::set_vault_authority(ctx, new_authority)
     }
 
     /// Toggle legacy trading enabled/disabled. Admin only.
@@ -43,6 +76,62 @@ pub mod stellalpha_vault {
         instructions::admin::toggle_legacy_trading(ctx)
     }
 
+    /// Propose new platform and performance fee rates (capped). Admin only.
+    /// Applied via apply_fee_change after the timelock.
+    pub fn propose_fee_change(ctx: Context<AdminGlobalConfig>, platform_fee_bps: u16, performance_fee_bps: u16) -> Result<()> {
+        instructions::admin::propose_fee_change(ctx, platform_fee_bps, performance_fee_bps)
+    }
+
+    /// Apply the pending fee change once the timelock has expired. Admin only.
+    pub fn apply_fee_change(ctx: Context<AdminGlobalConfig>) -> Result<()> {
+        instructions::admin::apply_fee_change(ctx)
+    }
+
+    /// Propose a new admin (two-step handover). Admin only.
+    pub fn propose_admin(ctx: Context<AdminGlobalConfig>, new_admin: Pubkey) -> Result<()> {
+        instructions::admin::propose_admin(ctx, new_admin)
+    }
+
+    /// Accept the admin role. Pending admin only.
+    pub fn accept_admin(ctx: Context<AcceptAdmin>) -> Result<()> {
+        instructions::admin::accept_admin(ctx)
+    }
+
+    /// Approve a swap program for execute_trader_swap. Admin only.
+    pub fn add_swap_program(ctx: Context<AdminGlobalConfig>, program_id: Pubkey) -> Result<()> {
+        instructions::admin::add_swap_program(ctx, program_id)
+    }
+
+    /// Revoke a swap program. Admin only.
+    pub fn remove_swap_program(ctx: Context<AdminGlobalConfig>, program_id: Pubkey) -> Result<()> {
+        instructions::admin::remove_swap_program(ctx, program_id)
+    }
+
+    /// Add a protocol-wide default allowed mint. Admin only.
+    pub fn add_default_mint(ctx: Context<AdminGlobalConfig>, mint: Pubkey) -> Result<()> {
+        instructions::admin::add_default_mint(ctx, mint)
+    }
+
+    /// Remove a protocol-wide default allowed mint. Admin only.
+    pub fn remove_default_mint(ctx: Context<AdminGlobalConfig>, mint: Pubkey) -> Result<()> {
+        instructions::admin::remove_default_mint(ctx, mint)
+    }
+
+    /// Set the guardian allowed to halt trading. Admin only.
+    pub fn set_guardian(ctx: Context<AdminGlobalConfig>, new_guardian: Pubkey) -> Result<()> {
+        instructions::admin::set_guardian(ctx, new_guardian)
+    }
+
+    /// Emergency halt of all backend trading. Guardian or admin.
+    pub fn halt_trading(ctx: Context<GuardianGlobalConfig>) -> Result<()> {
+        instructions::admin::halt_trading(ctx)
+    }
+
+    /// Resume backend trading after a halt. Admin only.
+    pub fn resume_trading(ctx: Context<AdminGlobalConfig>) -> Result<()> {
+        instructions::admin::resume_trading(ctx)
+    }
+
     pub fn deposit_token(ctx: Context<DepositToken>, amount: u64) -> Result<()> {
         instructions::vault::deposit_token(ctx, amount)
     }
@@ -74,8 +163,31 @@ pub mod stellalpha_vault {
     // Trader Instructions
     // ===================================
 
-    pub fn create_trader_state(ctx: Context<CreateTraderState>, amount: u64) -> Result<()> {
-        instructions::trader::create_trader_state(ctx, amount)
+    /// Create a TraderState allocation. allocation_id 0 is the default allocation;
+    /// other ids allow several allocations to the same trader.
+    /// Also creates the allocation's TradeJournal.
+    pub fn create_trader_state(ctx: Context<CreateTraderState>, amount: u64, allocation_id: u16) -> Result<()> {
+        instructions::trader::create_trader_state(ctx, amount, allocation_id)
+    }
+
+    /// Add Base Asset capital to an existing TraderState. Owner only.
+    /// Raises current_value and high_water_mark equally (no performance fee on deposits).
+    pub fn fund_trader_state(ctx: Context<FundTraderState>, amount: u64) -> Result<()> {
+        instructions::trader::fund_trader_state(ctx, amount)
+    }
+
+    /// Migrate a TraderState from an older account layout. Owner only.
+    /// Migrated accounts become allocation 0 and keep their address.
+    /// Every non-base ATA is passed with its TraderPosition PDA to register it;
+    /// current_value not held in the Base Asset ATA becomes their cost basis.
+    pub fn migrate_trader_state<'info>(ctx: Context<'_, '_, 'info, 'info, MigrateTraderState<'info>>) -> Result<()> {
+        instructions::trader::migrate_trader_state(ctx)
+    }
+
+    /// Create the TradeJournal for a TraderState that predates journals. Permissionless.
+    /// Required before such a TraderState can swap, close or withdraw.
+    pub fn init_trade_journal(ctx: Context<InitTradeJournal>) -> Result<()> {
+        instructions::trader::init_trade_journal(ctx)
     }
 
     pub fn pause_trader_state(ctx: Context<UpdateTraderState>) -> Result<()> {
@@ -86,10 +198,41 @@ pub mod stellalpha_vault {
         instructions::trader::resume_trader_state(ctx)
     }
 
+    /// Owner-only. Moves the TraderState to Settling; only swaps into the Base Asset are allowed.
+    pub fn begin_trader_settlement(ctx: Context<UpdateTraderState>) -> Result<()> {
+        instructions::trader::begin_trader_settlement(ctx)
+    }
+
+    /// Set per-TraderState risk limits in bps. Owner only. 0 disables a limit.
+    pub fn set_risk_limits(ctx: Context<UpdateTraderState>, max_trade_bps: u16, max_slippage_bps: u16) -> Result<()> {
+        instructions::trader::set_risk_limits(ctx, max_trade_bps, max_slippage_bps)
+    }
+
+    /// Set the drawdown (bps from peak) that auto-pauses a TraderState. Owner only. 0 disables it.
+    pub fn set_max_drawdown(ctx: Context<UpdateTraderState>, max_drawdown_bps: u16) -> Result<()> {
+        instructions::trader::set_max_drawdown(ctx, max_drawdown_bps)
+    }
+
+    /// Set per-day swap count and Base Asset volume ceilings. Owner only. 0 disables a limit.
+    /// Each day is a fixed window opened by its first swap, not a rolling 24 hours.
+    pub fn set_rate_limits(ctx: Context<UpdateTraderState>, max_swaps_per_window: u32, max_volume_per_window: u64) -> Result<()> {
+        instructions::trader::set_rate_limits(ctx, max_swaps_per_window, max_volume_per_window)
+    }
+
+    /// Owner-only. Requires Settled; refunds the Base Asset to the UserVault.
     pub fn close_trader_state(ctx: Context<CloseTraderState>) -> Result<()> {
         instructions::trader::close_trader_state(ctx)
     }
 
+    /// Partial withdrawal of Base Asset without closing the allocation. Owner only.
+    /// Requires every position unwound to the Base Asset and no sync or settlement
+    /// in progress, paused or not.
+    /// Charges the performance fee on the withdrawn share of gains above the HWM;
+    /// current_value and high_water_mark shrink proportionally.
+    pub fn partial_withdraw_trader_state(ctx: Context<PartialWithdrawTraderState>, amount: u64) -> Result<()> {
+        instructions::trader::partial_withdraw_trader_state(ctx, amount)
+    }
+
     // =========================================================================
     // PHASE 7: Multi-Asset Support
     // =========================================================================
@@ -101,16 +244,31 @@ pub mod stellalpha_vault {
     }
 
     /// Phase 7D: Mark TraderState as initialized after portfolio sync.
-    /// Can be called by owner OR backend authority (one-time only).
+    /// Can be called by owner OR backend authority (one-time only, from Created).
     /// NOTE: Prefer finish_trader_sync for explicit sync lifecycle.
     pub fn mark_trader_initialized(ctx: Context<MarkTraderInitialized>) -> Result<()> {
         instructions::trader::mark_trader_initialized(ctx)
     }
 
+    /// Phase 7C: Start portfolio sync phase. Backend authority only.
+    pub fn start_trader_sync(ctx: Context<TraderSync>) -> Result<()> {
+        instructions::trader::start_trader_sync(ctx)
+    }
+
+    /// Phase 7C: Finish portfolio sync and enable automated trading. Backend authority only.
+    pub fn finish_trader_sync(ctx: Context<TraderSync>) -> Result<()> {
+        instructions::trader::finish_trader_sync(ctx)
+    }
+
+    /// Abort a sync that passed its deadline. Owner only; leaves the TraderState paused.
+    pub fn abort_trader_sync(ctx: Context<UpdateTraderState>) -> Result<()> {
+        instructions::trader::abort_trader_sync(ctx)
+    }
+
 
     /// Phase 7.1: Close a non-base TraderState ATA to reclaim rent.
-    /// Owner-only. Requires is_paused = true. ATA balance must be 0.
-    /// Rent returned to owner.
+    /// Owner-only. Requires Paused or Settled and an empty TraderPosition.
+    /// Any untracked balance is swept to owner_token_account. Rent returned to owner.
     pub fn close_trader_ata(ctx: Context<CloseTraderAtaContext>) -> Result<()> {
         instructions::trader::close_trader_ata(ctx)
     }
@@ -118,19 +276,35 @@ pub mod stellalpha_vault {
     /// Execute a swap on behalf of a TraderState via Jupiter CPI.
     /// amount_in: Total amount to spend, including platform fee.
     /// min_amount_out: Minimum amount to receive (slippage protection).
+    /// expected_amount_out: Quoted output, checked against the TraderState slippage limit.
     /// data: Opaque data blob for Jupiter swap instruction.
-    pub fn execute_trader_swap(ctx: Context<ExecuteTraderSwap>, amount_in: u64, min_amount_out: u64, data: Vec<u8>) -> Result<()> {
-        instructions::swap::execute_trader_swap(ctx, amount_in, min_amount_out, data)
+    /// Each swap is appended to the TraderState's TradeJournal.
+    pub fn execute_trader_swap(
+        ctx: Context<ExecuteTraderSwap>,
+        amount_in: u64,
+        min_amount_out: u64,
+        expected_amount_out: u64,
+        data: Vec<u8>,
+    ) -> Result<()> {
+        instructions::swap::execute_trader_swap(ctx, amount_in, min_amount_out, expected_amount_out, data)
     }
 
     /// settlement: Validate that TraderState holds only Base Asset and amount >= current_value.
-    /// Locks the state as 'Settled' to enable withdrawal.
+    /// Remaining accounts: every (TraderPosition, ATA) pair; each ATA must be empty or closed.
+    /// Charges the performance fee on equity above the high-water mark.
+    /// Moves a Paused or Settling TraderState to Settled to enable withdrawal.
     pub fn settle_trader_state(ctx: Context<SettleTraderState>) -> Result<()> {
         instructions::trader::settle_trader_state(ctx)
     }
 
+    /// Periodic performance fee crystallization without exiting the allocation.
+    /// Backend authority only. Requires all funds to be held in Base Asset.
+    pub fn crystallize_performance_fee(ctx: Context<CrystallizePerformanceFee>) -> Result<()> {
+        instructions::trader::crystallize_performance_fee(ctx)
+    }
+
     /// withdraw: Exit flow.
-    /// Prerequisites: Paused && Settled.
+    /// Prerequisites: TraderStatus::Settled, with every non-base ATA closed.
     /// Flow: TraderState -> UserVault -> User Wallet.
     /// Closes TraderState and its ATA.
     pub fn withdraw_trader_state(ctx: Context<WithdrawTraderState>) -> Result<()> {
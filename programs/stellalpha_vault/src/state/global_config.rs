@@ -1,4 +1,5 @@
 use anchor_lang::prelude::*;
+use crate::constants::{MAX_SWAP_PROGRAMS, MAX_DEFAULT_MINTS};
 
 /// Global configuration for the protocol.
 /// Admin-controlled settings for fees and feature flags.
@@ -9,11 +10,38 @@ pub struct GlobalConfig {
     pub performance_fee_bps: u16,
     /// If false, legacy execute_swap is disabled. Default: false.
     pub legacy_trading_enabled: bool,
+    /// Proposed fee schedule, applied via apply_fee_change after the timelock.
+    pub pending_platform_fee_bps: u16,
+    pub pending_performance_fee_bps: u16,
+    /// Unix timestamp after which the pending fees may be applied. 0 = no pending change.
+    pub fee_change_effective_at: i64,
+    /// Proposed new admin. Must sign accept_admin to take over.
+    pub pending_admin: Option<Pubkey>,
+    /// Swap programs execute_trader_swap may CPI into with the TraderState PDA as signer.
+    pub approved_swap_programs: Vec<Pubkey>,
+    /// Protocol-wide mint whitelist used when a vault's allowed_mints is empty.
+    pub default_allowed_mints: Vec<Pubkey>,
+    /// Emergency switch. Halts backend trading, TraderState creation and sync.
+    /// Owner exit paths are never gated by this flag.
+    pub trading_halted: bool,
+    /// May halt trading alongside the admin. Only the admin can resume.
+    pub guardian: Pubkey,
 }
 
 impl GlobalConfig {
     // 8 discriminator + 32 admin + 2 platform_fee + 2 performance_fee + 1 legacy_flag
-    pub const SPACE: usize = 8 + 32 + 2 + 2 + 1;
+    // + 2 pending_platform_fee + 2 pending_performance_fee + 8 fee_change_effective_at
+    // + 33 pending_admin (Option<Pubkey>)
+    // + 4 vec_len + (32 * MAX_SWAP_PROGRAMS) approved_swap_programs
+    // + 4 vec_len + (32 * MAX_DEFAULT_MINTS) default_allowed_mints
+    // + 1 trading_halted + 32 guardian
+    pub const SPACE: usize = 8 + 32 + 2 + 2 + 1 + 2 + 2 + 8 + 33
+        + 4 + (32 * MAX_SWAP_PROGRAMS)
+        + 4 + (32 * MAX_DEFAULT_MINTS)
+        + 1 + 32;
+
+    // Original layout: admin, platform_fee_bps, performance_fee_bps, legacy_trading_enabled.
+    pub const LEGACY_SPACE: usize = 8 + 32 + 2 + 2 + 1;
 }
 
 /// Event emitted when legacy trading is toggled.
@@ -22,3 +50,75 @@ pub struct LegacyTradingToggled {
     pub enabled: bool,
     pub admin: Pubkey,
 }
+
+/// Event emitted when a fee change is proposed.
+#[event]
+pub struct FeeChangeProposed {
+    pub platform_fee_bps: u16,
+    pub performance_fee_bps: u16,
+    pub effective_at: i64,
+    pub admin: Pubkey,
+}
+
+/// Event emitted when platform/performance fee rates are updated.
+#[event]
+pub struct FeeConfigUpdated {
+    pub old_platform_fee_bps: u16,
+    pub new_platform_fee_bps: u16,
+    pub old_performance_fee_bps: u16,
+    pub new_performance_fee_bps: u16,
+    pub admin: Pubkey,
+}
+
+/// Event emitted when the admin proposes a successor.
+#[event]
+pub struct AdminTransferProposed {
+    pub admin: Pubkey,
+    pub pending_admin: Pubkey,
+}
+
+/// Event emitted when the pending admin accepts the role.
+#[event]
+pub struct AdminTransferred {
+    pub old_admin: Pubkey,
+    pub new_admin: Pubkey,
+}
+
+/// Event emitted when a swap program is approved or revoked.
+#[event]
+pub struct SwapProgramUpdated {
+    pub program_id: Pubkey,
+    pub approved: bool,
+    pub admin: Pubkey,
+}
+
+/// Event emitted when a protocol-wide default mint is added or removed.
+#[event]
+pub struct DefaultMintUpdated {
+    pub mint: Pubkey,
+    pub allowed: bool,
+    pub admin: Pubkey,
+}
+
+/// Event emitted when protocol trading is halted or resumed.
+#[event]
+pub struct TradingHaltUpdated {
+    pub halted: bool,
+    pub signer: Pubkey,
+}
+
+/// Event emitted when the guardian is changed.
+#[event]
+pub struct GuardianUpdated {
+    pub old_guardian: Pubkey,
+    pub new_guardian: Pubkey,
+    pub admin: Pubkey,
+}
+
+/// Event emitted when the GlobalConfig is migrated from the original layout.
+#[event]
+pub struct GlobalConfigMigrated {
+    pub admin: Pubkey,
+    pub platform_fee_bps: u16,
+    pub performance_fee_bps: u16,
+}
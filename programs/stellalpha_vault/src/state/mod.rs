@@ -4,7 +4,11 @@
 pub mod user_vault;
 pub mod global_config;
 pub mod trader_state;
+pub mod trader_position;
+pub mod trade_journal;
 
 pub use user_vault::*;
 pub use global_config::*;
 pub use trader_state::*;
+pub use trader_position::*;
+pub use trade_journal::*;
@@ -0,0 +1,80 @@
+use anchor_lang::prelude::*;
+use crate::constants::TRADE_JOURNAL_CAPACITY;
+
+/// One execute_trader_swap, as recorded in the TradeJournal.
+#[zero_copy]
+#[derive(Default)]
+pub struct JournalEntry {
+    /// Slot the swap executed in.
+    pub slot: u64,
+    pub input_mint: Pubkey,
+    pub output_mint: Pubkey,
+    /// Input tokens consumed by the swap (excluding fee).
+    pub amount_spent: u64,
+    /// Output tokens received.
+    pub amount_received: u64,
+    /// Platform fee taken from the input side.
+    pub fee: u64,
+    /// 1 for a portfolio-sync swap, 0 for copy trading.
+    pub is_sync: u8,
+    pub _padding: [u8; 7],
+}
+
+/// Fixed-size, zero-copy history of the most recent swaps of a TraderState.
+/// PDA: [b"trade_journal", trader_state].
+///
+/// Ring buffer: `head` is the next slot to write; once full, the oldest entry
+/// is overwritten. Entries in order, oldest first, are
+/// `entries[(head + i) % CAPACITY]` for i in 0..len() when full, else 0..len().
+#[account(zero_copy)]
+pub struct TradeJournal {
+    /// The TraderState this journal belongs to.
+    pub trader_state: Pubkey,
+
+    /// Swaps recorded since creation (not capped).
+    pub total_entries: u64,
+
+    /// Index the next entry is written to.
+    pub head: u32,
+
+    /// PDA Bump.
+    pub bump: u8,
+
+    pub _padding: [u8; 3],
+
+    pub entries: [JournalEntry; TRADE_JOURNAL_CAPACITY],
+}
+
+impl TradeJournal {
+    // 8 discriminator
+    // + 32 (trader_state)
+    // + 8 (total_entries)
+    // + 4 (head) + 1 (bump) + 3 (padding)
+    // + CAPACITY * (8 slot + 32 input_mint + 32 output_mint + 8 spent + 8 received + 8 fee
+    //              + 1 is_sync + 7 padding)
+    pub const INIT_SPACE: usize = 8 + 32 + 8 + 4 + 1 + 3 + TRADE_JOURNAL_CAPACITY * (8 + 32 + 32 + 8 + 8 + 8 + 1 + 7);
+
+    /// Number of entries currently held.
+    pub fn len(&self) -> usize {
+        (self.total_entries as usize).min(TRADE_JOURNAL_CAPACITY)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.total_entries == 0
+    }
+
+    /// Record a swap, overwriting the oldest entry once the journal is full.
+    pub fn append(&mut self, entry: JournalEntry) {
+        let head = self.head as usize % TRADE_JOURNAL_CAPACITY;
+        self.entries[head] = entry;
+        self.head = ((head + 1) % TRADE_JOURNAL_CAPACITY) as u32;
+        self.total_entries = self.total_entries.saturating_add(1);
+    }
+}
+
+/// Event emitted when a TradeJournal is created for an existing TraderState.
+#[event]
+pub struct TradeJournalInitialized {
+    pub trade_journal: Pubkey,
+    pub trader_state: Pubkey,
+}
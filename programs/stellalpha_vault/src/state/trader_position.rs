@@ -0,0 +1,82 @@
+use anchor_lang::prelude::*;
+use crate::errors::ErrorCode;
+use crate::state::TraderState;
+
+/// Non-base holding of a TraderState, valued at cost.
+/// PDA: [b"trader_position", trader_state, mint].
+///
+/// Created alongside the TraderState ATA for `mint` (create_trader_ata) and
+/// updated by execute_trader_swap whenever `mint` is a swap side.
+#[account]
+pub struct TraderPosition {
+    /// The TraderState holding this position.
+    pub trader_state: Pubkey,
+
+    /// Token mint of the position.
+    pub mint: Pubkey,
+
+    /// PDA Bump.
+    pub bump: u8,
+
+    /// Token amount held (raw units).
+    pub quantity: u64,
+
+    /// Base Asset paid for `quantity`.
+    pub cost_basis: u64,
+}
+
+impl TraderPosition {
+    // 8 discriminator
+    // + 32 (trader_state) + 32 (mint)
+    // + 1 (bump)
+    // + 8 (quantity)
+    // + 8 (cost_basis)
+    pub const INIT_SPACE: usize = 8 + 32 + 32 + 1 + 8 + 8;
+
+    pub fn is_open(&self) -> bool {
+        self.quantity > 0
+    }
+
+    pub fn add(&mut self, quantity: u64, cost: u64) -> Result<()> {
+        self.quantity = self.quantity.checked_add(quantity).ok_or(ErrorCode::MathOverflow)?;
+        self.cost_basis = self.cost_basis.checked_add(cost).ok_or(ErrorCode::MathOverflow)?;
+        Ok(())
+    }
+
+    /// Cost basis that removing `quantity` would release.
+    /// Quantity beyond what is tracked (e.g. airdrops) carries no cost.
+    pub fn cost_of(&self, quantity: u64) -> Result<u64> {
+        let removed = quantity.min(self.quantity);
+        if removed == self.quantity {
+            Ok(self.cost_basis)
+        } else {
+            TraderState::proportional_share(self.cost_basis, removed, self.quantity)
+        }
+    }
+
+    /// Remove `quantity` and return the cost basis released.
+    pub fn remove(&mut self, quantity: u64) -> Result<u64> {
+        let cost = self.cost_of(quantity)?;
+        self.quantity -= quantity.min(self.quantity);
+        self.cost_basis -= cost;
+        Ok(cost)
+    }
+}
+
+/// Event emitted when a TraderPosition and its ATA are created.
+#[event]
+pub struct TraderPositionCreated {
+    pub trader_state: Pubkey,
+    pub mint: Pubkey,
+    pub position_count: u16,
+}
+
+/// Event emitted when a position ATA and its TraderPosition are closed.
+/// `swept` is the untracked balance moved to the owner before closing.
+#[event]
+pub struct TraderPositionClosed {
+    pub trader_state: Pubkey,
+    pub mint: Pubkey,
+    pub position_count: u16,
+    pub swept: u64,
+}
@@ -1,21 +1,93 @@
 use anchor_lang::prelude::*;
+use crate::errors::ErrorCode;
+use crate::constants::RATE_LIMIT_WINDOW;
+use crate::state::TraderPosition;
+
+/// Lifecycle status of a TraderState.
+///
+/// Happy path: Created -> Syncing -> Active -> Paused | Settling -> Settled -> Closed.
+/// See `can_transition_to` for the full table.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum TraderStatus {
+    /// Funded, waiting for portfolio sync.
+    #[default]
+    Created,
+    /// Backend is performing portfolio sync swaps.
+    Syncing,
+    /// Automated copy trading enabled.
+    Active,
+    /// Stopped by the owner (or the drawdown stop). No swaps.
+    Paused,
+    /// Unwinding to Base Asset. Only swaps into the Base Asset.
+    Settling,
+    /// All funds confirmed in Base Asset. Ready for withdrawal.
+    Settled,
+    /// Withdrawn or closed. Terminal.
+    Closed,
+}
+
+impl TraderStatus {
+    pub fn can_transition_to(self, next: TraderStatus) -> bool {
+        use TraderStatus::*;
+        matches!(
+            (self, next),
+            (Created, Syncing | Active | Paused | Settling)
+                | (Syncing, Active | Paused)
+                | (Active, Paused | Settling)
+                | (Paused, Created | Active | Settling | Settled)
+                | (Settling, Paused | Settled)
+                | (Settled, Closed)
+        )
+    }
+
+    /// Typed error for an illegal transition from `self` to `next`.
+    pub fn transition_error(self, next: TraderStatus) -> ErrorCode {
+        use TraderStatus::*;
+        match (self, next) {
+            (Closed, _) => ErrorCode::TraderClosed,
+            (Settled, _) => ErrorCode::AlreadySettled,
+            (Syncing, Syncing | Settling) => ErrorCode::AlreadySyncing,
+            (Paused, Syncing) => ErrorCode::TraderPaused,
+            (Settling, _) => ErrorCode::TraderSettling,
+            (_, Syncing) | (Active, Active) => ErrorCode::AlreadyInitialized,
+            (_, Settled) => ErrorCode::TraderNotPaused,
+            (_, Closed) => ErrorCode::NotSettled,
+            _ => ErrorCode::InvalidStatusTransition,
+        }
+    }
+
+    /// Require Active, with a typed error for every other status.
+    pub fn require_active(self) -> Result<()> {
+        match self {
+            TraderStatus::Active => Ok(()),
+            TraderStatus::Created => err!(ErrorCode::TraderNotInitialized),
+            TraderStatus::Syncing => err!(ErrorCode::AlreadySyncing),
+            TraderStatus::Paused => err!(ErrorCode::TraderPaused),
+            TraderStatus::Settling => err!(ErrorCode::TraderSettling),
+            TraderStatus::Settled => err!(ErrorCode::AlreadySettled),
+            TraderStatus::Closed => err!(ErrorCode::TraderClosed),
+        }
+    }
+}
 
 /// Per-trader allocation managed by backend authority.
+/// PDA: [b"trader_state", owner, trader, allocation_seed].
 /// 
-/// # Lifecycle
+/// # Lifecycle (TraderStatus)
 /// 1. Created by owner with initial funding
-/// 2. Backend starts sync phase (is_syncing = true)
+/// 2. Backend starts sync phase (Syncing)
 /// 3. Backend performs portfolio sync swaps
-/// 4. Backend finishes sync (is_initialized = true)
+/// 4. Backend finishes sync (Active)
 /// 5. Automated trading enabled
-/// 6. Owner pauses when ready to exit
-/// 7. Backend settles to base asset
-/// 8. Owner withdraws funds
+/// 6. Owner pauses (Paused) or starts settlement (Settling) when ready to exit
+/// 7. Backend settles to base asset (Settled)
+/// 8. Owner withdraws funds (Closed)
 /// 
 /// # Authority Model
-/// - Owner: create, pause, resume, close, withdraw
-/// - Backend (vault.authority): start_sync, finish_sync, execute swaps, settle
+/// - Owner: create, pause, resume, begin settlement, close, withdraw, abort an expired sync
+/// - Backend (vault.authority): start_sync, finish_sync, execute swaps, crystallize fees
 #[account]
+#[derive(Default)]
 pub struct TraderState {
     /// The user who owns this allocation and the funds.
     pub owner: Pubkey,
@@ -39,14 +111,63 @@ pub struct TraderState {
     /// Used for analytics and reporting.
     pub cumulative_profit: i64,
     
-    /// Safety switch for this specific allocation.
-    pub is_paused: bool, 
+    /// Lifecycle status. Every instruction checks it; changes go through `transition`.
+    pub status: TraderStatus,
+
+    /// Status restored by resume_trader_state. Recorded on every move to Paused;
+    /// an interrupted sync resumes as Created.
+    pub resume_status: TraderStatus,
+
+    /// Unix timestamp of the last performance fee crystallization.
+    /// Initialized to creation time.
+    pub last_crystallized_at: i64,
+
+    /// Distinguishes multiple allocations to the same trader by one owner.
+    /// Allocation 0 derives the pre-allocation-id PDA address.
+    pub allocation_id: u16,
+
+    /// Owner-set cap on a single swap's size, in bps of current_value.
+    /// A non-base input is sized at the cost basis it removes. 0 = no limit.
+    pub max_trade_bps: u16,
+
+    /// Owner-set cap on slippage of min_amount_out vs the expected output, in bps.
+    /// 0 = no limit.
+    pub max_slippage_bps: u16,
 
-    /// True only when all TraderState funds are confirmed to be in base_mint.
-    /// Required before withdrawal.
-    pub is_settled: bool,
+    /// Highest base-denominated value observed; reference for the drawdown stop.
+    /// Moves with capital added or withdrawn.
+    pub peak_value: u64,
 
-    pub is_initialized: bool,
+    /// Owner-set drawdown from peak_value, in bps, that auto-pauses the
+    /// TraderState when realized on a swap into Base Asset. 0 = disabled.
+    pub max_drawdown_bps: u16,
+
+    /// Start of the current fixed rate-limit window (unix timestamp).
+    /// Set by the first swap after the previous window ended.
+    pub window_start: i64,
+
+    /// Swaps executed in the current window.
+    pub window_swap_count: u32,
+
+    /// Base-denominated swap volume in the current window.
+    pub window_volume: u64,
+
+    /// Owner-set ceiling on swaps per window. 0 = no limit.
+    pub max_swaps_per_window: u32,
+
+    /// Owner-set ceiling on base-denominated volume per window. 0 = no limit.
+    pub max_volume_per_window: u64,
+
+    /// Number of TraderPosition accounts with a non-zero quantity.
+    /// current_value = base holdings + cost basis of open positions.
+    pub open_positions: u16,
+
+    /// Number of TraderPosition accounts (and their ATAs) in existence.
+    /// Settlement must be shown every one of them.
+    pub position_count: u16,
+
+    /// Unix timestamp after which sync swaps stop and the owner may abort sync.
+    pub sync_deadline: i64,
 }
 
 impl TraderState {
@@ -56,8 +177,404 @@ impl TraderState {
     // + 8 (current_value)
     // + 8 (high_water_mark)
     // + 8 (cumulative_profit)
-    // + 1 (is_paused)
-    // + 1 (is_settled)
-    // + 1 (is_initialized)
-    pub const INIT_SPACE: usize = 8 + 32 + 32 + 32 + 1 + 8 + 8 + 8 + 1 + 1 + 1;
+    // + 1 (status) + 1 (resume_status)
+    // + 8 (last_crystallized_at)
+    // + 2 (allocation_id)
+    // + 2 (max_trade_bps)
+    // + 2 (max_slippage_bps)
+    // + 8 (peak_value)
+    // + 2 (max_drawdown_bps)
+    // + 8 (window_start)
+    // + 4 (window_swap_count)
+    // + 8 (window_volume)
+    // + 4 (max_swaps_per_window)
+    // + 8 (max_volume_per_window)
+    // + 2 (open_positions)
+    // + 2 (position_count)
+    // + 8 (sync_deadline)
+    pub const INIT_SPACE: usize = 8 + 32 + 32 + 32 + 1 + 8 + 8 + 8 + 1 + 1 + 8 + 2 + 2 + 2 + 8 + 2
+        + 8 + 4 + 8 + 4 + 8
+        + 2 + 2
+        + 8;
+
+    // Original layout: owner, trader, vault, bump, current_value,
+    // high_water_mark, cumulative_profit, is_paused, is_settled, is_initialized.
+    pub const LEGACY_SPACE: usize = 8 + 32 + 32 + 32 + 1 + 8 + 8 + 8 + 1 + 1 + 1;
+
+    /// Move to `next` if the transition table allows it.
+    pub fn transition(&mut self, next: TraderStatus) -> Result<()> {
+        if !self.status.can_transition_to(next) {
+            return Err(self.status.transition_error(next).into());
+        }
+        if next == TraderStatus::Paused {
+            self.resume_status = match self.status {
+                TraderStatus::Syncing => TraderStatus::Created,
+                status => status,
+            };
+        }
+        self.status = next;
+        Ok(())
+    }
+
+    pub fn is_syncing(&self) -> bool {
+        self.status == TraderStatus::Syncing
+    }
+
+    /// PDA seed suffix for an allocation id.
+    /// Allocation 0 contributes no bytes, so it derives the same address as
+    /// TraderStates created before allocation ids existed.
+    pub fn seed_for_allocation(allocation_id: u16) -> Vec<u8> {
+        if allocation_id == 0 {
+            Vec::new()
+        } else {
+            allocation_id.to_le_bytes().to_vec()
+        }
+    }
+
+    pub fn allocation_seed(&self) -> Vec<u8> {
+        Self::seed_for_allocation(self.allocation_id)
+    }
+
+    /// Enforce the owner's risk limits on a swap request.
+    /// `trade_size` is the swap's Base Asset value: amount_in when spending Base
+    /// Asset, otherwise the cost basis the input TraderPosition would release.
+    /// Portfolio sync deploys the whole allocation, so trade size is not capped then.
+    pub fn check_risk_limits(
+        &self,
+        trade_size: u64,
+        min_amount_out: u64,
+        expected_amount_out: u64,
+    ) -> Result<()> {
+        if self.max_trade_bps > 0 && !self.is_syncing() {
+            let max_trade = (self.current_value as u128)
+                .checked_mul(self.max_trade_bps as u128)
+                .ok_or(ErrorCode::MathOverflow)?
+                .checked_div(10000)
+                .ok_or(ErrorCode::MathOverflow)?;
+            require!(trade_size as u128 <= max_trade, ErrorCode::TradeSizeExceeded);
+        }
+
+        if self.max_slippage_bps > 0 {
+            // The quote sets the floor, so it cannot be left out or undercut
+            require!(
+                expected_amount_out > 0 && min_amount_out <= expected_amount_out,
+                ErrorCode::InvalidQuote
+            );
+            let floor = (expected_amount_out as u128)
+                .checked_mul(10000 - self.max_slippage_bps as u128)
+                .ok_or(ErrorCode::MathOverflow)?
+                .checked_div(10000)
+                .ok_or(ErrorCode::MathOverflow)?;
+            require!(min_amount_out as u128 >= floor, ErrorCode::SlippageLimitExceeded);
+        }
+        Ok(())
+    }
+
+    /// Book a completed swap at cost and return the realized PnL.
+    /// A `None` position means that side of the swap is the Base Asset.
+    ///
+    /// - Base -> token: extends the position at `amount_spent`; the fee is realized.
+    /// - Token -> base: closes `amount_spent + fee` of the position; PnL is
+    ///   `amount_received` minus the cost basis removed.
+    /// - Token -> token: cost basis carries over; nothing is realized.
+    pub fn apply_swap(
+        &mut self,
+        input_position: Option<&mut TraderPosition>,
+        output_position: Option<&mut TraderPosition>,
+        amount_spent: u64,
+        fee: u64,
+        amount_received: u64,
+    ) -> Result<i64> {
+        let outflow = amount_spent.checked_add(fee).ok_or(ErrorCode::MathOverflow)?;
+        let input_is_base = input_position.is_none();
+
+        // Cost of what left the TraderState
+        let cost_out = match input_position {
+            None => outflow,
+            Some(position) => {
+                let was_open = position.is_open();
+                let cost = position.remove(outflow)?;
+                if was_open && !position.is_open() {
+                    self.open_positions = self.open_positions.checked_sub(1).ok_or(ErrorCode::MathOverflow)?;
+                }
+                cost
+            }
+        };
+
+        // Cost assigned to what arrived; the remainder is realized
+        let cost_in = match output_position {
+            None => amount_received,
+            Some(_) if amount_received == 0 => 0,
+            Some(position) => {
+                // Fees paid in Base Asset are realized immediately
+                let carried = if input_is_base { amount_spent } else { cost_out };
+                if !position.is_open() {
+                    self.open_positions = self.open_positions.checked_add(1).ok_or(ErrorCode::MathOverflow)?;
+                }
+                position.add(amount_received, carried)?;
+                carried
+            }
+        };
+
+        let realized = i64::try_from(cost_in as i128 - cost_out as i128).map_err(|_| ErrorCode::MathOverflow)?;
+        let new_value = (self.current_value as i128 + realized as i128).max(0);
+        self.current_value = u64::try_from(new_value).map_err(|_| ErrorCode::MathOverflow)?;
+        self.cumulative_profit = self.cumulative_profit.checked_add(realized).ok_or(ErrorCode::MathOverflow)?;
+        Ok(realized)
+    }
+
+    /// Count a swap against the fixed rate-limit window, starting a new window
+    /// (and resetting both counters) once RATE_LIMIT_WINDOW has elapsed.
+    /// `base_volume` is the Base Asset leg of the swap (0 for token-to-token swaps).
+    pub fn record_swap(&mut self, now: i64, base_volume: u64) -> Result<()> {
+        if now >= self.window_start.saturating_add(RATE_LIMIT_WINDOW) {
+            self.window_start = now;
+            self.window_swap_count = 0;
+            self.window_volume = 0;
+        }
+
+        self.window_swap_count = self.window_swap_count.checked_add(1).ok_or(ErrorCode::MathOverflow)?;
+        self.window_volume = self.window_volume.checked_add(base_volume).ok_or(ErrorCode::MathOverflow)?;
+
+        require!(
+            self.max_swaps_per_window == 0 || self.window_swap_count <= self.max_swaps_per_window,
+            ErrorCode::SwapCountLimitExceeded
+        );
+        require!(
+            self.max_volume_per_window == 0 || self.window_volume <= self.max_volume_per_window,
+            ErrorCode::SwapVolumeLimitExceeded
+        );
+        Ok(())
+    }
+
+    /// Record a realized base-denominated value and apply the drawdown stop.
+    /// Returns true if the stop was triggered (the TraderState is now paused).
+    pub fn record_base_value(&mut self) -> Result<bool> {
+        if self.current_value > self.peak_value {
+            self.peak_value = self.current_value;
+        }
+        if self.max_drawdown_bps == 0 {
+            return Ok(false);
+        }
+
+        let floor = (self.peak_value as u128)
+            .checked_mul(10000 - self.max_drawdown_bps as u128)
+            .ok_or(ErrorCode::MathOverflow)?
+            .checked_div(10000)
+            .ok_or(ErrorCode::MathOverflow)?;
+        if (self.current_value as u128) < floor && self.status == TraderStatus::Active {
+            self.transition(TraderStatus::Paused)?;
+            return Ok(true);
+        }
+        Ok(false)
+    }
+
+    /// Performance fee owed on the withdrawn fraction of gains above the HWM.
+    /// Call before `reduce_capital`, which removes that fraction of the HWM.
+    pub fn withdrawal_performance_fee(&mut self, amount: u64, performance_fee_bps: u16) -> Result<u64> {
+        require!(amount <= self.current_value, ErrorCode::InsufficientFunds);
+        if amount == 0 || self.current_value <= self.high_water_mark {
+            return Ok(0);
+        }
+
+        let withdrawn_gain = Self::proportional_share(
+            self.current_value - self.high_water_mark,
+            amount,
+            self.current_value,
+        )?;
+        let fee = Self::proportional_share(withdrawn_gain, performance_fee_bps as u64, 10000)?;
+
+        self.cumulative_profit = self
+            .cumulative_profit
+            .checked_sub(i64::try_from(fee).map_err(|_| ErrorCode::MathOverflow)?)
+            .ok_or(ErrorCode::MathOverflow)?;
+        Ok(fee)
+    }
+
+    /// Remove `amount` of capital from the allocation.
+    /// HWM and peak_value shrink in the same proportion as current_value so the
+    /// remaining capital keeps its performance fee and drawdown baselines.
+    pub fn reduce_capital(&mut self, amount: u64) -> Result<()> {
+        require!(amount <= self.current_value, ErrorCode::InsufficientFunds);
+        if amount == 0 {
+            return Ok(());
+        }
+
+        let hwm_reduction = Self::proportional_share(self.high_water_mark, amount, self.current_value)?;
+        let peak_reduction = Self::proportional_share(self.peak_value, amount, self.current_value)?;
+
+        self.high_water_mark = self.high_water_mark.saturating_sub(hwm_reduction);
+        self.peak_value = self.peak_value.saturating_sub(peak_reduction);
+        self.current_value -= amount;
+        Ok(())
+    }
+
+    /// value * part / whole, in u128.
+    pub(crate) fn proportional_share(value: u64, part: u64, whole: u64) -> Result<u64> {
+        Ok((value as u128)
+            .checked_mul(part as u128)
+            .ok_or(ErrorCode::MathOverflow)?
+            .checked_div(whole as u128)
+            .ok_or(ErrorCode::MathOverflow)? as u64)
+    }
+
+    /// Crystallize the performance fee on equity above the high-water mark.
+    /// Returns the fee owed in base mint (0 if equity is at or below the HWM).
+    /// Advances HWM and current_value to post-fee equity and books the net gain.
+    pub fn crystallize_performance_fee(&mut self, equity: u64, performance_fee_bps: u16) -> Result<u64> {
+        if equity <= self.high_water_mark {
+            return Ok(0);
+        }
+
+        let profit = equity - self.high_water_mark;
+        let fee = (profit as u128)
+            .checked_mul(performance_fee_bps as u128)
+            .ok_or(ErrorCode::MathOverflow)?
+            .checked_div(10000)
+            .ok_or(ErrorCode::MathOverflow)? as u64;
+        // Swaps already realized PnL into current_value; only book base holdings
+        // beyond it (e.g. direct transfers) plus the fee itself.
+        let unbooked = equity as i128 - self.current_value as i128;
+        let net_profit = i64::try_from(unbooked - fee as i128).map_err(|_| ErrorCode::MathOverflow)?;
+
+        self.cumulative_profit = self
+            .cumulative_profit
+            .checked_add(net_profit)
+            .ok_or(ErrorCode::MathOverflow)?;
+        self.high_water_mark = equity - fee;
+        self.current_value = equity - fee;
+        // Fees paid out are not a trading loss; keep the drawdown reference net of them.
+        self.peak_value = self.peak_value.saturating_sub(fee).max(self.current_value);
+        Ok(fee)
+    }
+}
+
+/// Event emitted when a realized value breaches the owner's drawdown limit
+/// and the TraderState is auto-paused.
+#[event]
+pub struct DrawdownStopTriggered {
+    pub trader_state: Pubkey,
+    pub peak_value: u64,
+    pub current_value: u64,
+    pub max_drawdown_bps: u16,
+}
+
+/// Event emitted when a TraderState is created and funded from the UserVault.
+#[event]
+pub struct TraderStateCreated {
+    pub trader_state: Pubkey,
+    pub owner: Pubkey,
+    pub trader: Pubkey,
+    pub vault: Pubkey,
+    pub allocation_id: u16,
+    pub amount: u64,
+}
+
+/// Event emitted when the owner adds capital to a TraderState.
+#[event]
+pub struct TraderStateFunded {
+    pub trader_state: Pubkey,
+    pub amount: u64,
+    pub current_value: u64,
+    pub high_water_mark: u64,
+}
+
+/// Event emitted when the owner pauses a TraderState.
+#[event]
+pub struct TraderStatePaused {
+    pub trader_state: Pubkey,
+    pub resume_status: TraderStatus,
+}
+
+/// Event emitted when the owner resumes a paused TraderState.
+#[event]
+pub struct TraderStateResumed {
+    pub trader_state: Pubkey,
+    pub status: TraderStatus,
+}
+
+/// Event emitted on the remaining lifecycle transitions
+/// (sync start/finish/abort, mark initialized, begin settlement).
+#[event]
+pub struct TraderStatusChanged {
+    pub trader_state: Pubkey,
+    pub old_status: TraderStatus,
+    pub new_status: TraderStatus,
+}
+
+/// Event emitted when the owner changes risk, drawdown or rate limits.
+#[event]
+pub struct TraderLimitsUpdated {
+    pub trader_state: Pubkey,
+    pub max_trade_bps: u16,
+    pub max_slippage_bps: u16,
+    pub max_drawdown_bps: u16,
+    pub max_swaps_per_window: u32,
+    pub max_volume_per_window: u64,
+}
+
+/// Event emitted for every execute_trader_swap.
+/// amount_spent / amount_received are measured from token balances; fee is the
+/// platform fee taken from the input side before the swap.
+#[event]
+pub struct TraderSwapExecuted {
+    pub trader_state: Pubkey,
+    pub input_mint: Pubkey,
+    pub output_mint: Pubkey,
+    pub amount_in: u64,
+    pub fee: u64,
+    pub amount_spent: u64,
+    pub amount_received: u64,
+    pub realized_pnl: i64,
+    pub current_value: u64,
+    /// True for a portfolio-sync swap, false for copy trading.
+    pub is_sync: bool,
+}
+
+/// Event emitted when a performance fee is crystallized without exiting.
+#[event]
+pub struct PerformanceFeeCrystallized {
+    pub trader_state: Pubkey,
+    pub fee: u64,
+    pub high_water_mark: u64,
+    pub cumulative_profit: i64,
+}
+
+/// Event emitted when a TraderState is settled to the Base Asset.
+#[event]
+pub struct TraderStateSettled {
+    pub trader_state: Pubkey,
+    pub equity: u64,
+    pub performance_fee: u64,
+    pub high_water_mark: u64,
+    pub cumulative_profit: i64,
+}
+
+/// Event emitted when capital leaves a TraderState for the owner's wallet.
+/// `closed` is true for the full exit (withdraw_trader_state).
+#[event]
+pub struct TraderStateWithdrawn {
+    pub trader_state: Pubkey,
+    pub owner: Pubkey,
+    pub amount: u64,
+    pub current_value: u64,
+    pub closed: bool,
+}
+
+/// Event emitted when a paused TraderState is closed and refunded to the UserVault.
+#[event]
+pub struct TraderStateClosed {
+    pub trader_state: Pubkey,
+    pub owner: Pubkey,
+    pub amount: u64,
+}
+
+/// Event emitted when a legacy TraderState is migrated to the current layout.
+/// `position_count` counts the legacy ATAs registered as TraderPositions.
+#[event]
+pub struct TraderStateMigrated {
+    pub trader_state: Pubkey,
+    pub owner: Pubkey,
+    pub status: TraderStatus,
+    pub position_count: u16,
 }
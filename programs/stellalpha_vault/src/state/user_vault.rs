@@ -16,4 +16,92 @@ pub struct UserVault {
 impl UserVault {
     // Initial space buffer: 8 discriminator + 32 owner + 32 authority + 1 bump + 1 paused + 32 base_mint + 4 vec_len + (32 * 10 initial capacity)
     pub const INIT_SPACE: usize = 8 + 32 + 32 + 1 + 1 + 32 + 4 + (32 * 10); 
+
+    /// Account size needed to hold `mint_count` allowed mints. Never below INIT_SPACE.
+    pub fn space_for(mint_count: usize) -> usize {
+        (8 + 32 + 32 + 1 + 1 + 32 + 4 + (32 * mint_count)).max(Self::INIT_SPACE)
+    }
+
+    /// Account size needed once `mint` is whitelisted. Duplicates need no extra space.
+    pub fn space_with_mint(&self, mint: &Pubkey) -> usize {
+        let added = usize::from(!self.allowed_mints.contains(mint));
+        Self::space_for(self.allowed_mints.len() + added)
+    }
+
+    /// Base Asset is always allowed. Otherwise the vault's own whitelist applies,
+    /// falling back to the protocol-wide defaults when the vault list is empty.
+    pub fn is_mint_allowed(&self, mint: &Pubkey, default_allowed_mints: &[Pubkey]) -> bool {
+        if *mint == self.base_mint {
+            return true;
+        }
+        if self.allowed_mints.is_empty() {
+            default_allowed_mints.contains(mint)
+        } else {
+            self.allowed_mints.contains(mint)
+        }
+    }
+}
+
+/// Event emitted when the owner rotates the backend authority.
+#[event]
+pub struct VaultAuthorityUpdated {
+    pub vault: Pubkey,
+    pub old_authority: Pubkey,
+    pub new_authority: Pubkey,
+}
+
+/// Event emitted when a vault is created.
+#[event]
+pub struct VaultInitialized {
+    pub vault: Pubkey,
+    pub owner: Pubkey,
+    pub authority: Pubkey,
+    pub base_mint: Pubkey,
+}
+
+/// Event emitted when the owner pauses or unpauses the vault.
+#[event]
+pub struct VaultPauseUpdated {
+    pub vault: Pubkey,
+    pub paused: bool,
+}
+
+/// Event emitted when the owner deposits Base Asset into the vault.
+#[event]
+pub struct VaultDeposited {
+    pub vault: Pubkey,
+    pub mint: Pubkey,
+    pub amount: u64,
+}
+
+/// Event emitted when the owner withdraws Base Asset from the vault.
+#[event]
+pub struct VaultWithdrawn {
+    pub vault: Pubkey,
+    pub mint: Pubkey,
+    pub amount: u64,
+}
+
+/// Event emitted when the owner opens a vault token account (ATA).
+#[event]
+pub struct VaultAtaInitialized {
+    pub vault: Pubkey,
+    pub mint: Pubkey,
+    pub token_account: Pubkey,
+}
+
+/// Event emitted when the owner closes an empty vault token account.
+#[event]
+pub struct VaultAtaClosed {
+    pub vault: Pubkey,
+    pub mint: Pubkey,
+    pub token_account: Pubkey,
+}
+
+/// Event emitted when a mint is added to or removed from the vault whitelist.
+#[event]
+pub struct AllowedMintUpdated {
+    pub vault: Pubkey,
+    pub mint: Pubkey,
+    pub allowed: bool,
 }
@@ -9,7 +9,8 @@ import {
   getAccount, 
   TOKEN_PROGRAM_ID, 
   ASSOCIATED_TOKEN_PROGRAM_ID, 
-  getAssociatedTokenAddressSync
+  getAssociatedTokenAddressSync,
+  getOrCreateAssociatedTokenAccount
 } from "@solana/spl-token";
 
 import * as fs from "fs";
@@ -96,7 +97,7 @@ describe("Full E2E: Vault Creation → TraderState Lifecycle → Withdrawal", ()
       await program.account.globalConfig.fetch(globalConfigPda);
     } catch {
       await program.methods
-        .initializeGlobalConfig()
+        .initializeGlobalConfig(backendAuthority.publicKey)
         .accounts({
           globalConfig: globalConfigPda,
           admin: backendAuthority.publicKey,
@@ -193,7 +194,7 @@ describe("Full E2E: Vault Creation → TraderState Lifecycle → Withdrawal", ()
     console.log("\n▶ STEP 3: Create TraderState");
 
     await program.methods
-        .createTraderState(TRADER_ALLOCATION)
+        .createTraderState(TRADER_ALLOCATION, 0)
         .accounts({
             owner: user.publicKey,
             trader: trader.publicKey,
@@ -210,13 +211,13 @@ describe("Full E2E: Vault Creation → TraderState Lifecycle → Withdrawal", ()
         .rpc();
 
     const ts = await program.account.traderState.fetch(traderStatePda);
-    assert.equal(ts.isInitialized, false, "Should NOT be initialized");
+    assert.deepEqual(ts.status, { created: {} }, "Should NOT be initialized");
     assert.ok(ts.currentValue.eq(TRADER_ALLOCATION));
     
     const traderBalance = (await getAccount(provider.connection, traderTokenAccount)).amount;
     console.log("   ✓ TraderState created with", TRADER_ALLOCATION.toNumber() / 1e6, "tokens");
     console.log("   TraderState balance:", Number(traderBalance) / 1e6, "tokens");
-    console.log("   is_initialized:", ts.isInitialized);
+    console.log("   status:", Object.keys(ts.status)[0]);
   });
 
   // ============================================================
@@ -236,9 +237,9 @@ describe("Full E2E: Vault Creation → TraderState Lifecycle → Withdrawal", ()
       .rpc();
 
     const ts = await program.account.traderState.fetch(traderStatePda);
-    assert.equal(ts.isInitialized, true);
+    assert.deepEqual(ts.status, { active: {} });
     console.log("   ✓ TraderState initialized");
-    console.log("   is_initialized:", ts.isInitialized);
+    console.log("   status:", Object.keys(ts.status)[0]);
   });
 
   // ============================================================
@@ -257,17 +258,33 @@ describe("Full E2E: Vault Creation → TraderState Lifecycle → Withdrawal", ()
         .rpc();
     
     const ts = await program.account.traderState.fetch(traderStatePda);
-    assert.equal(ts.isPaused, true);
+    assert.deepEqual(ts.status, { paused: {} });
     console.log("   ✓ TraderState paused");
-    console.log("   is_paused:", ts.isPaused);
+    console.log("   status:", Object.keys(ts.status)[0]);
   });
 
   // ============================================================
-  // STEP 7: User closes TraderState (funds return to vault)
+  // STEP 7: User settles and closes TraderState (funds return to vault)
   // ============================================================
   it("Step 7: User closes TraderState → funds return to vault", async () => {
     console.log("\n▶ STEP 7: Close TraderState");
 
+    // Closing requires settlement, which charges any performance fee first
+    const adminFeeAccount = await getOrCreateAssociatedTokenAccount(
+      provider.connection, backendAuthority, baseMint, backendAuthority.publicKey
+    );
+    await program.methods
+        .settleTraderState()
+        .accounts({
+            owner: user.publicKey,
+            vault: vaultPda,
+            traderState: traderStatePda,
+            traderTokenAccount: traderTokenAccount,
+            performanceFeeAccount: adminFeeAccount.address,
+        })
+        .signers([user])
+        .rpc();
+
     const vaultBalanceBefore = (await getAccount(provider.connection, vaultTokenAccount)).amount;
     const traderBalanceBefore = (await getAccount(provider.connection, traderTokenAccount)).amount;
 
@@ -2,6 +2,14 @@ import * as anchor from "@coral-xyz/anchor";
 import { Program } from "@coral-xyz/anchor";
 import { StellalphaVault } from "../target/types/stellalpha_vault";
 import { assert } from "chai";
+import {
+  createMint,
+  mintTo,
+  getOrCreateAssociatedTokenAccount,
+  getAssociatedTokenAddressSync,
+  TOKEN_PROGRAM_ID,
+  ASSOCIATED_TOKEN_PROGRAM_ID
+} from "@solana/spl-token";
 
 describe("Phase 1: Global Config", () => {
   const provider = anchor.AnchorProvider.env();
@@ -10,6 +18,7 @@ describe("Phase 1: Global Config", () => {
   const program = anchor.workspace.StellalphaVault as Program<StellalphaVault>;
 
   const admin = provider.wallet;
+  const guardian = anchor.web3.Keypair.generate();
 
   it("Is initialized!", async () => {
     // 1. Derive Global Config PDA
@@ -19,15 +28,17 @@ describe("Phase 1: Global Config", () => {
     );
 
     // 2. Initialize
+    let created = false;
     try {
         await program.methods
-          .initializeGlobalConfig()
+          .initializeGlobalConfig(guardian.publicKey)
           .accounts({
             globalConfig: globalConfigPda,
             admin: admin.publicKey,
             systemProgram: anchor.web3.SystemProgram.programId,
           })
           .rpc();
+        created = true;
     } catch (e) {
         console.log("Global Config might be already initialized");
     }
@@ -42,6 +53,9 @@ describe("Phase 1: Global Config", () => {
     assert.ok(account.admin.equals(admin.publicKey), "Admin matches signer");
     assert.ok(account.platformFeeBps === 10, "Platform fee is 10 bps");
     assert.ok(account.performanceFeeBps === 2000, "Performance fee is 2000 bps");
+    if (created) {
+      assert.ok(account.guardian.equals(guardian.publicKey), "Guardian is the key passed in, not the admin");
+    }
   });
 
   it("Enforces Singleton (Cannot Initialize Twice)", async () => {
@@ -52,7 +66,7 @@ describe("Phase 1: Global Config", () => {
 
     try {
       await program.methods
-        .initializeGlobalConfig()
+        .initializeGlobalConfig(guardian.publicKey)
         .accounts({
           globalConfig: globalConfigPda,
           admin: admin.publicKey,
@@ -64,4 +78,294 @@ describe("Phase 1: Global Config", () => {
       assert.include(e.message, "already in use", "Expected 'already in use' error");
     }
   });
+
+  it("Rejects Migrating a Global Config Already on the Current Layout", async () => {
+    const [globalConfigPda] = anchor.web3.PublicKey.findProgramAddressSync(
+      [Buffer.from("global_config")],
+      program.programId
+    );
+
+    try {
+      await program.methods
+        .migrateGlobalConfig()
+        .accounts({ globalConfig: globalConfigPda, admin: admin.publicKey })
+        .rpc();
+      assert.fail("Current-layout config should not migrate");
+    } catch (e) {
+      assert.include(e.message, "already uses the current account layout");
+    }
+  });
+
+  it("Rejects Fee Proposals Above Hard Caps", async () => {
+    const [globalConfigPda] = anchor.web3.PublicKey.findProgramAddressSync(
+      [Buffer.from("global_config")],
+      program.programId
+    );
+
+    try {
+      await program.methods
+        .proposeFeeChange(10_000, 2000)
+        .accounts({ globalConfig: globalConfigPda, admin: admin.publicKey })
+        .rpc();
+      assert.fail("Should have rejected 100% platform fee");
+    } catch (e) {
+      assert.include(e.message, "Fee exceeds the protocol maximum");
+    }
+  });
+
+  it("Timelocks Proposed Fee Changes", async () => {
+    const [globalConfigPda] = anchor.web3.PublicKey.findProgramAddressSync(
+      [Buffer.from("global_config")],
+      program.programId
+    );
+
+    await program.methods
+      .proposeFeeChange(20, 1500)
+      .accounts({ globalConfig: globalConfigPda, admin: admin.publicKey })
+      .rpc();
+
+    let account = await program.account.globalConfig.fetch(globalConfigPda);
+    assert.equal(account.pendingPlatformFeeBps, 20);
+    assert.equal(account.pendingPerformanceFeeBps, 1500);
+    assert.isAbove(account.feeChangeEffectiveAt.toNumber(), 0);
+    // Current rates untouched until applied
+    assert.equal(account.platformFeeBps, 10);
+    assert.equal(account.performanceFeeBps, 2000);
+
+    try {
+      await program.methods
+        .applyFeeChange()
+        .accounts({ globalConfig: globalConfigPda, admin: admin.publicKey })
+        .rpc();
+      assert.fail("Should have failed TimelockNotExpired");
+    } catch (e) {
+      assert.include(e.message, "Fee change timelock has not expired");
+    }
+
+    // Re-propose current defaults so downstream suites see no pending change in effect
+    await program.methods
+      .proposeFeeChange(10, 2000)
+      .accounts({ globalConfig: globalConfigPda, admin: admin.publicKey })
+      .rpc();
+  });
+
+  it("Hands Over Admin in Two Steps", async () => {
+    const [globalConfigPda] = anchor.web3.PublicKey.findProgramAddressSync(
+      [Buffer.from("global_config")],
+      program.programId
+    );
+    const newAdmin = anchor.web3.Keypair.generate();
+    const impostor = anchor.web3.Keypair.generate();
+
+    // A guardian left on the outgoing admin follows the role
+    await program.methods
+      .setGuardian(admin.publicKey)
+      .accounts({ globalConfig: globalConfigPda, admin: admin.publicKey })
+      .rpc();
+
+    await program.methods
+      .proposeAdmin(newAdmin.publicKey)
+      .accounts({ globalConfig: globalConfigPda, admin: admin.publicKey })
+      .rpc();
+
+    let account = await program.account.globalConfig.fetch(globalConfigPda);
+    assert.ok(account.admin.equals(admin.publicKey), "Admin unchanged until accepted");
+    assert.ok(account.pendingAdmin.equals(newAdmin.publicKey));
+
+    try {
+      await program.methods
+        .acceptAdmin()
+        .accounts({ globalConfig: globalConfigPda, pendingAdmin: impostor.publicKey })
+        .signers([impostor])
+        .rpc();
+      assert.fail("Only the pending admin can accept");
+    } catch (e) {
+      assert.include(e.message, "You are not authorized");
+    }
+
+    await program.methods
+      .acceptAdmin()
+      .accounts({ globalConfig: globalConfigPda, pendingAdmin: newAdmin.publicKey })
+      .signers([newAdmin])
+      .rpc();
+
+    account = await program.account.globalConfig.fetch(globalConfigPda);
+    assert.ok(account.admin.equals(newAdmin.publicKey));
+    assert.isNull(account.pendingAdmin);
+    assert.ok(account.guardian.equals(newAdmin.publicKey), "Guardian moves off the outgoing admin");
+
+    // Hand back to the original admin for downstream suites
+    await program.methods
+      .proposeAdmin(admin.publicKey)
+      .accounts({ globalConfig: globalConfigPda, admin: newAdmin.publicKey })
+      .signers([newAdmin])
+      .rpc();
+    await program.methods
+      .acceptAdmin()
+      .accounts({ globalConfig: globalConfigPda, pendingAdmin: admin.publicKey })
+      .rpc();
+
+    account = await program.account.globalConfig.fetch(globalConfigPda);
+    assert.ok(account.admin.equals(admin.publicKey));
+    assert.ok(account.guardian.equals(admin.publicKey));
+
+    // A separately held guardian is left alone
+    await program.methods
+      .setGuardian(guardian.publicKey)
+      .accounts({ globalConfig: globalConfigPda, admin: admin.publicKey })
+      .rpc();
+    await program.methods
+      .proposeAdmin(newAdmin.publicKey)
+      .accounts({ globalConfig: globalConfigPda, admin: admin.publicKey })
+      .rpc();
+    await program.methods
+      .acceptAdmin()
+      .accounts({ globalConfig: globalConfigPda, pendingAdmin: newAdmin.publicKey })
+      .signers([newAdmin])
+      .rpc();
+    account = await program.account.globalConfig.fetch(globalConfigPda);
+    assert.ok(account.guardian.equals(guardian.publicKey));
+
+    await program.methods
+      .proposeAdmin(admin.publicKey)
+      .accounts({ globalConfig: globalConfigPda, admin: newAdmin.publicKey })
+      .signers([newAdmin])
+      .rpc();
+    await program.methods
+      .acceptAdmin()
+      .accounts({ globalConfig: globalConfigPda, pendingAdmin: admin.publicKey })
+      .rpc();
+  });
+
+  it("Guardian Halts Trading; Only Admin Resumes", async () => {
+    const [globalConfigPda] = anchor.web3.PublicKey.findProgramAddressSync(
+      [Buffer.from("global_config")],
+      program.programId
+    );
+
+    // A funded TraderState to trade and top up while halted; the admin is the backend authority
+    const payer = (provider.wallet as anchor.Wallet).payer;
+    const vaultOwner = anchor.web3.Keypair.generate();
+    const trader = anchor.web3.Keypair.generate();
+    await provider.sendAndConfirm(new anchor.web3.Transaction().add(
+      anchor.web3.SystemProgram.transfer({
+        fromPubkey: admin.publicKey, toPubkey: vaultOwner.publicKey, lamports: 100_000_000
+      })
+    ));
+    const baseMint = await createMint(provider.connection, payer, payer.publicKey, null, 6);
+    const [vaultPda] = anchor.web3.PublicKey.findProgramAddressSync(
+      [Buffer.from("user_vault_v1"), vaultOwner.publicKey.toBuffer()],
+      program.programId
+    );
+    await program.methods
+      .initializeVault(admin.publicKey, baseMint)
+      .accounts({ vault: vaultPda, owner: vaultOwner.publicKey, systemProgram: anchor.web3.SystemProgram.programId })
+      .signers([vaultOwner])
+      .rpc();
+    const vaultTokenAccount = getAssociatedTokenAddressSync(baseMint, vaultPda, true);
+    await program.methods
+      .initVaultAta()
+      .accounts({
+        vault: vaultPda, owner: vaultOwner.publicKey, mint: baseMint,
+        vaultTokenAccount: vaultTokenAccount, tokenProgram: TOKEN_PROGRAM_ID,
+        associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID, systemProgram: anchor.web3.SystemProgram.programId
+      })
+      .signers([vaultOwner])
+      .rpc();
+    const ownerAta = await getOrCreateAssociatedTokenAccount(provider.connection, payer, baseMint, vaultOwner.publicKey);
+    await mintTo(provider.connection, payer, baseMint, ownerAta.address, payer.publicKey, 2_000_000);
+    await program.methods
+      .depositToken(new anchor.BN(2_000_000))
+      .accounts({
+        vault: vaultPda, owner: vaultOwner.publicKey, ownerTokenAccount: ownerAta.address,
+        vaultTokenAccount: vaultTokenAccount, tokenProgram: TOKEN_PROGRAM_ID
+      })
+      .signers([vaultOwner])
+      .rpc();
+    const [traderStatePda] = anchor.web3.PublicKey.findProgramAddressSync(
+      [Buffer.from("trader_state"), vaultOwner.publicKey.toBuffer(), trader.publicKey.toBuffer()],
+      program.programId
+    );
+    const traderTokenAccount = getAssociatedTokenAddressSync(baseMint, traderStatePda, true);
+    await program.methods
+      .createTraderState(new anchor.BN(1_000_000), 0)
+      .accounts({
+        owner: vaultOwner.publicKey, trader: trader.publicKey, vault: vaultPda,
+        traderState: traderStatePda, vaultTokenAccount: vaultTokenAccount,
+        traderTokenAccount: traderTokenAccount, mint: baseMint,
+        systemProgram: anchor.web3.SystemProgram.programId,
+        tokenProgram: TOKEN_PROGRAM_ID, associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID
+      })
+      .signers([vaultOwner])
+      .rpc();
+    await program.methods
+      .setGuardian(guardian.publicKey)
+      .accounts({ globalConfig: globalConfigPda, admin: admin.publicKey })
+      .rpc();
+
+    await program.methods
+      .haltTrading()
+      .accounts({ globalConfig: globalConfigPda, signer: guardian.publicKey })
+      .signers([guardian])
+      .rpc();
+
+    let account = await program.account.globalConfig.fetch(globalConfigPda);
+    assert.isTrue(account.tradingHalted);
+
+    // Backend trading and new funding are rejected while halted
+    try {
+      await program.methods
+        .executeTraderSwap(new anchor.BN(1_000), new anchor.BN(0), new anchor.BN(0), Buffer.from([]))
+        .accounts({
+          authority: admin.publicKey,
+          vault: vaultPda,
+          traderState: traderStatePda,
+          inputTokenAccount: traderTokenAccount,
+          outputTokenAccount: traderTokenAccount,
+          platformFeeAccount: traderTokenAccount,
+          globalConfig: globalConfigPda,
+          jupiterProgram: anchor.web3.SystemProgram.programId,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          instructions: anchor.web3.SYSVAR_INSTRUCTIONS_PUBKEY,
+        })
+        .rpc();
+      assert.fail("Swaps must be rejected while trading is halted");
+    } catch (e) {
+      assert.include(e.message, "Protocol trading is halted");
+    }
+
+    try {
+      await program.methods
+        .fundTraderState(new anchor.BN(1_000))
+        .accounts({
+          owner: vaultOwner.publicKey, vault: vaultPda, traderState: traderStatePda,
+          vaultTokenAccount: vaultTokenAccount, traderTokenAccount: traderTokenAccount,
+          tokenProgram: TOKEN_PROGRAM_ID
+        })
+        .signers([vaultOwner])
+        .rpc();
+      assert.fail("Funding must be rejected while trading is halted");
+    } catch (e) {
+      assert.include(e.message, "Protocol trading is halted");
+    }
+
+    try {
+      await program.methods
+        .resumeTrading()
+        .accounts({ globalConfig: globalConfigPda, admin: guardian.publicKey })
+        .signers([guardian])
+        .rpc();
+      assert.fail("Guardian must not be able to resume trading");
+    } catch (e) {
+      assert.include(e.message, "You are not authorized");
+    }
+
+    await program.methods
+      .resumeTrading()
+      .accounts({ globalConfig: globalConfigPda, admin: admin.publicKey })
+      .rpc();
+
+    account = await program.account.globalConfig.fetch(globalConfigPda);
+    assert.isFalse(account.tradingHalted);
+  });
 });
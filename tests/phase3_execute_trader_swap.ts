@@ -1,6 +1,7 @@
 import * as anchor from "@coral-xyz/anchor";
 import { Program } from "@coral-xyz/anchor";
 import { StellalphaVault } from "../target/types/stellalpha_vault";
+import { MockSwap } from "../target/types/mock_swap";
 import { assert } from "chai";
 import { 
   createMint, 
@@ -19,6 +20,7 @@ describe("Phase 3: Execute Trader Swap", () => {
   anchor.setProvider(provider);
 
   const program = anchor.workspace.StellalphaVault as Program<StellalphaVault>;
+  const mockSwap = anchor.workspace.MockSwap as Program<MockSwap>;
 
   // Main wallet (Payer / Backend Agent / Admin for GlobalConfig)
   const walletPath = os.homedir() + "/.config/solana/devnet-wallet.json";
@@ -34,15 +36,17 @@ describe("Phase 3: Execute Trader Swap", () => {
   let vaultPda: anchor.web3.PublicKey;
   let vaultTokenAccount: anchor.web3.PublicKey;
   let traderStatePda: anchor.web3.PublicKey;
-  let traderTokenAccount: anchor.web3.PublicKey; // Input
-  let traderOutputAccount: anchor.web3.PublicKey; // Output
+  let traderTokenAccount: anchor.web3.PublicKey; // Input (Base)
+  let quoteMint: anchor.web3.PublicKey;
+  let traderQuoteAccount: anchor.web3.PublicKey; // Output (Quote)
+  let traderQuotePosition: anchor.web3.PublicKey;
+  let poolBaseAccount: anchor.web3.PublicKey; // Mock pool's Base reserve (owned by Payer)
+  let poolQuoteAccount: anchor.web3.PublicKey; // Mock pool's Quote reserve (owned by Payer)
 
   const FUNDING_AMOUNT = new anchor.BN(1_000_000); 
   const SWAP_AMOUNT_IN = new anchor.BN(500_000);   
   const MIN_AMOUNT_OUT = new anchor.BN(400_000);   
 
-  const MEMO_PROGRAM_ID = new anchor.web3.PublicKey("MemoSq4gqABAXKb96qnH8TysNcWxMyWCqXgDLGmfcQb");
-
   before(async () => {
     console.log("Setting up Phase 3 test environment...");
     console.log("Payer:", payer.publicKey.toString());
@@ -90,7 +94,7 @@ describe("Phase 3: Execute Trader Swap", () => {
     );
     try {
         await program.methods
-          .initializeGlobalConfig()
+          .initializeGlobalConfig(payer.publicKey)
           .accounts({
             globalConfig: globalConfigPda,
             admin: payer.publicKey,
@@ -112,6 +116,11 @@ describe("Phase 3: Execute Trader Swap", () => {
         }
     }
 
+    // Swap program must be on the GlobalConfig allowlist (idempotent)
+    await program.methods.addSwapProgram(mockSwap.programId).accounts({
+        globalConfig: globalConfigPda, admin: payer.publicKey
+    }).signers([payer]).rpc();
+
     // 3. Initialize UserVault (Fresh Owner)
     [vaultPda] = anchor.web3.PublicKey.findProgramAddressSync(
         [Buffer.from("user_vault_v1"), vaultOwner.publicKey.toBuffer()],
@@ -158,17 +167,9 @@ describe("Phase 3: Execute Trader Swap", () => {
         program.programId
     );
     traderTokenAccount = getAssociatedTokenAddressSync(baseMint, traderStatePda, true);
-    
-    traderOutputAccount = await createAccount(
-        provider.connection,
-        payer, // Payer pays rent
-        baseMint,
-        traderStatePda, // Owned by TraderState
-        anchor.web3.Keypair.generate() 
-    );
 
     // Initial funding
-    await program.methods.createTraderState(FUNDING_AMOUNT).accounts({
+    await program.methods.createTraderState(FUNDING_AMOUNT, 0).accounts({
         owner: vaultOwner.publicKey, trader: trader.publicKey, vault: vaultPda,
         traderState: traderStatePda, vaultTokenAccount: vaultTokenAccount,
         traderTokenAccount: traderTokenAccount, mint: baseMint,
@@ -186,52 +187,137 @@ describe("Phase 3: Execute Trader Swap", () => {
         })
         .signers([vaultOwner])
         .rpc();
+
+    // 6. Quote position to swap into, and a mock_swap pool that pays it out
+    quoteMint = await createMint(provider.connection, payer, payer.publicKey, null, 6);
+    await program.methods.addAllowedMint(quoteMint)
+        .accounts({ owner: vaultOwner.publicKey, vault: vaultPda })
+        .signers([vaultOwner]).rpc();
+    traderQuoteAccount = getAssociatedTokenAddressSync(quoteMint, traderStatePda, true);
+    [traderQuotePosition] = anchor.web3.PublicKey.findProgramAddressSync(
+        [Buffer.from("trader_position"), traderStatePda.toBuffer(), quoteMint.toBuffer()],
+        program.programId
+    );
+    await program.methods.createTraderAta().accounts({
+        owner: vaultOwner.publicKey, vault: vaultPda, traderState: traderStatePda, mint: quoteMint,
+        traderTokenAccount: traderQuoteAccount, traderPosition: traderQuotePosition,
+        tokenProgram: TOKEN_PROGRAM_ID, associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+        systemProgram: anchor.web3.SystemProgram.programId
+    }).signers([vaultOwner]).rpc();
+
+    poolBaseAccount = await createAccount(provider.connection, payer, baseMint, payer.publicKey, anchor.web3.Keypair.generate());
+    poolQuoteAccount = await createAccount(provider.connection, payer, quoteMint, payer.publicKey);
+    await mintTo(provider.connection, payer, quoteMint, poolQuoteAccount, payer.publicKey, 10_000_000);
+  });
+
+  const globalConfig = () =>
+    anchor.web3.PublicKey.findProgramAddressSync([Buffer.from("global_config")], program.programId)[0];
+
+  // mock_swap takes swap_amount (amount_in less the platform fee) of Base and pays `amountOut` Quote
+  const mockSwapIx = (swapAmount: anchor.BN, amountOut: anchor.BN) =>
+    mockSwap.methods.swapPair(swapAmount, amountOut)
+        .accounts({
+            authority: traderStatePda,
+            input: traderTokenAccount,
+            output: traderQuoteAccount,
+            poolAuthority: payer.publicKey,
+            poolInput: poolBaseAccount,
+            poolOutput: poolQuoteAccount,
+            tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .instruction();
+
+  const swapAccounts = (overrides: Record<string, anchor.web3.PublicKey | null> = {}) => ({
+    authority: vaultOwner.publicKey, // Vault Authority (Backend Agent)
+    vault: vaultPda,
+    traderState: traderStatePda,
+    inputTokenAccount: traderTokenAccount,
+    outputTokenAccount: traderQuoteAccount,
+    outputPosition: traderQuotePosition,
+    platformFeeAccount: ownerTokenAccount, // Admin Wallet ATA (owned by Payer)
+    globalConfig: globalConfig(),
+    jupiterProgram: mockSwap.programId,
+    tokenProgram: TOKEN_PROGRAM_ID,
+    instructions: anchor.web3.SYSVAR_INSTRUCTIONS_PUBKEY,
+    ...overrides,
   });
 
   it("Executes Trader Swap (Happy Path)", async () => {
     // 1. Calculate Expected Fee
     // Config: 10 bps. 500,000 * 10 / 10000 = 500.
     // Swap Amount = 499,500.
-    
-    const [globalConfigPda] = anchor.web3.PublicKey.findProgramAddressSync(
-      [Buffer.from("global_config")],
-      program.programId
-    );
-    
+    const expectedFee = BigInt(500);
+    const swapAmount = BigInt(SWAP_AMOUNT_IN.toString()) - expectedFee;
+
     const balanceInBefore = (await getAccount(provider.connection, traderTokenAccount)).amount;
+    const balanceOutBefore = (await getAccount(provider.connection, traderQuoteAccount)).amount;
     const feeBalanceBefore = (await getAccount(provider.connection, ownerTokenAccount)).amount;
+    const stateBefore = await program.account.traderState.fetch(traderStatePda);
 
-    await program.methods.executeTraderSwap(SWAP_AMOUNT_IN, MIN_AMOUNT_OUT, Buffer.from("MEMO"))
-        .accounts({
-            authority: vaultOwner.publicKey, // Vault Authority (Backend Agent)
-            vault: vaultPda,
-            traderState: traderStatePda,
-            inputTokenAccount: traderTokenAccount,
-            outputTokenAccount: traderOutputAccount,
-            platformFeeAccount: ownerTokenAccount, // Admin Wallet ATA (owned by Payer)
-            globalConfig: globalConfigPda,
-            jupiterProgram: MEMO_PROGRAM_ID,
-            tokenProgram: TOKEN_PROGRAM_ID,
-            instructions: anchor.web3.SYSVAR_INSTRUCTIONS_PUBKEY,
-        })
-        .signers([vaultOwner]) // Authority
+    const ix = await mockSwapIx(new anchor.BN(swapAmount.toString()), MIN_AMOUNT_OUT);
+    await program.methods.executeTraderSwap(SWAP_AMOUNT_IN, MIN_AMOUNT_OUT, MIN_AMOUNT_OUT, ix.data)
+        .accounts(swapAccounts())
+        .remainingAccounts(ix.keys)
+        .signers([vaultOwner, payer]) // Authority, pool authority
         .rpc();
         
     const balanceInAfter = (await getAccount(provider.connection, traderTokenAccount)).amount;
-    const balanceOutAfter = (await getAccount(provider.connection, traderOutputAccount)).amount;
+    const balanceOutAfter = (await getAccount(provider.connection, traderQuoteAccount)).amount;
     const feeBalanceAfter = (await getAccount(provider.connection, ownerTokenAccount)).amount;
+    const stateAfter = await program.account.traderState.fetch(traderStatePda);
 
     // Verify Fee
-    const expectedFee = BigInt(500);
     assert.equal(feeBalanceAfter - feeBalanceBefore, expectedFee, "Fee should be paid to admin");
 
     // Verify Swap
-    const swapAmount = BigInt(SWAP_AMOUNT_IN.toString()) - expectedFee; // 499,500
     // Input account decreases by AmountIn (Fee + Swap)
     assert.equal(balanceInBefore - balanceInAfter, BigInt(SWAP_AMOUNT_IN.toString()), "Input should decrease by Amount In");
-    
-    // Output account increases by SwapAmount
-    assert.equal(balanceOutAfter, swapAmount, "Output should receive Swap Amount (Simulated)");
+    // Output account increases by the pool payout
+    assert.equal(balanceOutAfter - balanceOutBefore, BigInt(MIN_AMOUNT_OUT.toString()), "Output should receive the pool payout");
+
+    // Only the fee is realized; the swap amount becomes the Quote position's cost
+    assert.equal(
+        stateBefore.currentValue.sub(stateAfter.currentValue).toString(),
+        expectedFee.toString(),
+        "current_value should drop by the fee"
+    );
+    const position = await program.account.traderPosition.fetch(traderQuotePosition);
+    assert.equal(position.costBasis.toString(), swapAmount.toString());
+    assert.equal(position.quantity.toString(), MIN_AMOUNT_OUT.toString());
+  });
+
+  it("Rejects a token account that is not the TraderState's ATA", async () => {
+    // Owned by the TraderState, but not at its ATA address
+    const traderOutputAccount = await createAccount(
+        provider.connection,
+        payer, // Payer pays rent
+        quoteMint,
+        traderStatePda,
+        anchor.web3.Keypair.generate() 
+    );
+
+    try {
+        await program.methods.executeTraderSwap(SWAP_AMOUNT_IN, MIN_AMOUNT_OUT, MIN_AMOUNT_OUT, Buffer.alloc(0))
+        .accounts(swapAccounts({ outputTokenAccount: traderOutputAccount }))
+        .signers([vaultOwner])
+        .rpc();
+        assert.fail("Should have failed");
+    } catch (e) {
+        assert.include(e.message, "Swap token accounts must be the TraderState's ATAs");
+    }
+  });
+
+  it("Rejects a swap between the same mint", async () => {
+    // Base -> Base would only charge the platform fee
+    try {
+        await program.methods.executeTraderSwap(SWAP_AMOUNT_IN, new anchor.BN(0), new anchor.BN(0), Buffer.alloc(0))
+        .accounts(swapAccounts({ outputTokenAccount: traderTokenAccount, outputPosition: null }))
+        .signers([vaultOwner])
+        .rpc();
+        assert.fail("Should have failed");
+    } catch (e) {
+        assert.include(e.message, "Invalid topology");
+    }
   });
 
   it("Fails if Paused", async () => {
@@ -241,19 +327,8 @@ describe("Phase 3: Execute Trader Swap", () => {
     }).signers([vaultOwner]).rpc();
 
     try {
-        await program.methods.executeTraderSwap(SWAP_AMOUNT_IN, MIN_AMOUNT_OUT, Buffer.from("MEMO"))
-        .accounts({
-            authority: vaultOwner.publicKey,
-            vault: vaultPda,
-            traderState: traderStatePda,
-            inputTokenAccount: traderTokenAccount,
-            outputTokenAccount: traderOutputAccount,
-            platformFeeAccount: ownerTokenAccount,
-            globalConfig: anchor.web3.PublicKey.findProgramAddressSync([Buffer.from("global_config")], program.programId)[0],
-            jupiterProgram: MEMO_PROGRAM_ID,
-            tokenProgram: TOKEN_PROGRAM_ID,
-            instructions: anchor.web3.SYSVAR_INSTRUCTIONS_PUBKEY,
-        })
+        await program.methods.executeTraderSwap(SWAP_AMOUNT_IN, MIN_AMOUNT_OUT, MIN_AMOUNT_OUT, Buffer.alloc(0))
+        .accounts(swapAccounts())
         .signers([vaultOwner])
         .rpc();
         assert.fail("Should have failed");
@@ -267,4 +342,18 @@ describe("Phase 3: Execute Trader Swap", () => {
         traderState: traderStatePda
     }).signers([vaultOwner]).rpc();
   });
+
+  it("Fails with Unapproved Swap Program", async () => {
+    const rogueProgram = anchor.web3.Keypair.generate().publicKey;
+
+    try {
+        await program.methods.executeTraderSwap(SWAP_AMOUNT_IN, MIN_AMOUNT_OUT, MIN_AMOUNT_OUT, Buffer.alloc(0))
+        .accounts(swapAccounts({ jupiterProgram: rogueProgram }))
+        .signers([vaultOwner])
+        .rpc();
+        assert.fail("Should have failed");
+    } catch (e) {
+        assert.include(e.message, "Swap program is not on the approved list");
+    }
+  });
 });
@@ -9,10 +9,12 @@ import {
   getAccount, 
   TOKEN_PROGRAM_ID, 
   ASSOCIATED_TOKEN_PROGRAM_ID, 
-  getAssociatedTokenAddressSync
+  getAssociatedTokenAddressSync,
+  getOrCreateAssociatedTokenAccount
 } from "@solana/spl-token";
 import * as fs from "fs";
 import * as os from "os";
+import { logEvents, txEvents } from "./utils/events";
 
 describe("Phase 4: TraderState Accounting", () => {
   const provider = anchor.AnchorProvider.env();
@@ -35,6 +37,7 @@ describe("Phase 4: TraderState Accounting", () => {
   let traderStatePda: anchor.web3.PublicKey;
   let traderBaseAccount: anchor.web3.PublicKey; // Input/Output (Base)
   let traderQuoteAccount: anchor.web3.PublicKey; // Output/Input (Quote)
+  let traderQuotePosition: anchor.web3.PublicKey; // TraderPosition for Quote
 
   const FUNDING_AMOUNT = new anchor.BN(1_000_000); 
   const SWAP_AMOUNT_IN = new anchor.BN(500_000);   
@@ -115,7 +118,7 @@ describe("Phase 4: TraderState Accounting", () => {
     );
     try {
         await program.methods
-          .initializeGlobalConfig()
+          .initializeGlobalConfig(payer.publicKey)
           .accounts({
             globalConfig: globalConfigPda,
             admin: payer.publicKey,
@@ -125,6 +128,11 @@ describe("Phase 4: TraderState Accounting", () => {
           .rpc();
     } catch (e) {}
 
+    // Swap program must be on the GlobalConfig allowlist (idempotent)
+    await program.methods.addSwapProgram(MEMO_PROGRAM_ID).accounts({
+        globalConfig: globalConfigPda, admin: payer.publicKey
+    }).signers([payer]).rpc();
+
     [vaultPda] = anchor.web3.PublicKey.findProgramAddressSync(
         [Buffer.from("user_vault_v1"), vaultOwner.publicKey.toBuffer()],
         program.programId
@@ -133,6 +141,11 @@ describe("Phase 4: TraderState Accounting", () => {
         .accounts({ vault: vaultPda, owner: vaultOwner.publicKey, systemProgram: anchor.web3.SystemProgram.programId })
         .signers([vaultOwner]).rpc();
 
+    // Whitelist Quote mint for this vault (applies to all TraderStates)
+    await program.methods.addAllowedMint(quoteMint)
+        .accounts({ owner: vaultOwner.publicKey, vault: vaultPda })
+        .signers([vaultOwner]).rpc();
+
     vaultTokenAccount = getAssociatedTokenAddressSync(baseMint, vaultPda, true);
     await program.methods.initVaultAta().accounts({
         vault: vaultPda, owner: vaultOwner.publicKey, mint: baseMint,
@@ -152,19 +165,10 @@ describe("Phase 4: TraderState Accounting", () => {
         program.programId
     );
     traderBaseAccount = getAssociatedTokenAddressSync(baseMint, traderStatePda, true);
-    
-    // Create Quote Account for Trader
-    // Provide explicit keypair to avoid createAccount defaulting to ATA logic (which fails on off-curve owner)
-    const quoteAccountKeypair = anchor.web3.Keypair.generate();
-    traderQuoteAccount = await createAccount(
-        provider.connection, 
-        payer, 
-        quoteMint, 
-        traderStatePda,
-        quoteAccountKeypair
-    );
+    // Quote ATA for Trader (created by create_trader_ata below)
+    traderQuoteAccount = getAssociatedTokenAddressSync(quoteMint, traderStatePda, true);
 
-    await program.methods.createTraderState(FUNDING_AMOUNT).accounts({
+    await program.methods.createTraderState(FUNDING_AMOUNT, 0).accounts({
         owner: vaultOwner.publicKey, trader: trader.publicKey, vault: vaultPda,
         traderState: traderStatePda, vaultTokenAccount: vaultTokenAccount,
         traderTokenAccount: traderBaseAccount, mint: baseMint,
@@ -172,6 +176,30 @@ describe("Phase 4: TraderState Accounting", () => {
         tokenProgram: TOKEN_PROGRAM_ID, associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID
     }).signers([vaultOwner]).rpc();
 
+    // Quote position record (created alongside the Quote ATA)
+    [traderQuotePosition] = anchor.web3.PublicKey.findProgramAddressSync(
+        [Buffer.from("trader_position"), traderStatePda.toBuffer(), quoteMint.toBuffer()],
+        program.programId
+    );
+    const ataSig = await program.methods.createTraderAta().accounts({
+        owner: vaultOwner.publicKey,
+        vault: vaultPda,
+        traderState: traderStatePda,
+        mint: quoteMint,
+        traderTokenAccount: traderQuoteAccount,
+        traderPosition: traderQuotePosition,
+        tokenProgram: TOKEN_PROGRAM_ID,
+        associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+        systemProgram: anchor.web3.SystemProgram.programId
+    }).signers([vaultOwner]).rpc({ commitment: "confirmed" });
+
+    const ataTx = await provider.connection.getTransaction(ataSig, { commitment: "confirmed", maxSupportedTransactionVersion: 0 });
+    const positionEvent = logEvents(program, ataTx).find((e) => e.name === "traderPositionCreated");
+    assert.ok(positionEvent, "TraderPositionCreated should be emitted");
+    assert.ok(positionEvent.data.traderState.equals(traderStatePda));
+    assert.ok(positionEvent.data.mint.equals(quoteMint));
+    assert.equal(positionEvent.data.positionCount, 1);
+
     // Phase 7: Mark TraderState as initialized to satisfy invariant
     await program.methods
         .markTraderInitialized()
@@ -184,7 +212,7 @@ describe("Phase 4: TraderState Accounting", () => {
         .rpc();
   });
 
-  it("Does NOT update current_value when swapping Base -> Token", async () => {
+  it("Books only the platform fee when swapping Base -> Token", async () => {
     // Initial State
     const initialAccount = await program.account.traderState.fetch(traderStatePda);
     assert.equal(initialAccount.currentValue.toString(), FUNDING_AMOUNT.toString());
@@ -198,13 +226,14 @@ describe("Phase 4: TraderState Accounting", () => {
     // Need to fund Quote account manually? No, swap creates output? No, created in before().
     // We just execute.
     
-    await program.methods.executeTraderSwap(SWAP_AMOUNT_IN, new anchor.BN(0), Buffer.from("MEMO"))
+    const sig = await program.methods.executeTraderSwap(SWAP_AMOUNT_IN, new anchor.BN(0), new anchor.BN(0), Buffer.from("MEMO"))
         .accounts({
             authority: vaultOwner.publicKey,
             vault: vaultPda,
             traderState: traderStatePda,
             inputTokenAccount: traderBaseAccount, // Base
             outputTokenAccount: traderQuoteAccount, // Quote (Not Base)
+            outputPosition: traderQuotePosition,
             platformFeeAccount: ownerTokenAccount, // Admin (Base matches input)
             globalConfig: globalConfigPda,
             jupiterProgram: MEMO_PROGRAM_ID,
@@ -212,14 +241,43 @@ describe("Phase 4: TraderState Accounting", () => {
             instructions: anchor.web3.SYSVAR_INSTRUCTIONS_PUBKEY,
         })
         .signers([vaultOwner])
-        .rpc();
+        .rpc({ commitment: "confirmed" });
 
     const postAccount = await program.account.traderState.fetch(traderStatePda);
-    // Should be unchanged
-    assert.equal(postAccount.currentValue.toString(), FUNDING_AMOUNT.toString());
+    // Cost-basis NAV: spent base becomes position cost; only the 10 bps fee is realized
+    const expectedFee = SWAP_AMOUNT_IN.muln(10).divn(10_000);
+    assert.equal(postAccount.currentValue.toString(), FUNDING_AMOUNT.sub(expectedFee).toString());
+    assert.equal(postAccount.cumulativeProfit.toString(), expectedFee.neg().toString());
+
+    // Accounting is reconstructible from the TraderSwapExecuted event alone
+    const tx = await provider.connection.getTransaction(sig, { commitment: "confirmed", maxSupportedTransactionVersion: 0 });
+    const swapEvent = txEvents(program, tx).find((e) => e.name === "traderSwapExecuted");
+    assert.ok(swapEvent, "TraderSwapExecuted should be emitted");
+    assert.ok(swapEvent.data.traderState.equals(traderStatePda));
+    assert.ok(swapEvent.data.inputMint.equals(baseMint));
+    assert.ok(swapEvent.data.outputMint.equals(quoteMint));
+    assert.equal(swapEvent.data.amountIn.toString(), SWAP_AMOUNT_IN.toString());
+    assert.equal(swapEvent.data.fee.toString(), expectedFee.toString());
+    assert.equal(swapEvent.data.currentValue.toString(), postAccount.currentValue.toString());
+    assert.isFalse(swapEvent.data.isSync, "Copy trade is not a sync swap");
+
+    // The swap is also recorded in the TraderState's on-chain TradeJournal
+    const [tradeJournalPda] = anchor.web3.PublicKey.findProgramAddressSync(
+      [Buffer.from("trade_journal"), traderStatePda.toBuffer()],
+      program.programId
+    );
+    const journal = await program.account.tradeJournal.fetch(tradeJournalPda);
+    assert.ok(journal.traderState.equals(traderStatePda));
+    assert.equal(journal.totalEntries.toNumber(), 1);
+    assert.equal(journal.head, 1);
+    assert.ok(journal.entries[0].inputMint.equals(baseMint));
+    assert.ok(journal.entries[0].outputMint.equals(quoteMint));
+    assert.equal(journal.entries[0].fee.toString(), expectedFee.toString());
+    assert.equal(journal.entries[0].slot.toNumber(), tx.slot);
+    assert.equal(journal.entries[0].isSync, 0);
   });
 
-  it("Updates current_value when swapping Token -> Base", async () => {
+  it("Does not overwrite current_value when swapping Token -> Base", async () => {
     // Swap Quote -> Base
     // First, we need Quote tokens in Input Account.
     // Mint some mock Quote tokens to traderQuoteAccount (bypass vault for test setup).
@@ -232,20 +290,22 @@ describe("Phase 4: TraderState Accounting", () => {
 
     // Execute Swap
     // Input: Quote. Output: Base.
-    // Output Mint == Base. Logic SHOULD update.
-    // Mock won't transfer. `amount_received` = 0.
-    // Expect `current_value` -> 0.
+    // Output Mint == Base. Mock won't transfer: `amount_received` = 0.
+    // Quote was minted directly (no cost basis), so nothing is realized.
     
     // NOTE: `execute_trader_swap` expects `platform_fee_account` to match Input Mint.
     
     const [globalConfigPda] = anchor.web3.PublicKey.findProgramAddressSync([Buffer.from("global_config")], program.programId);
 
-    await program.methods.executeTraderSwap(amountIn, new anchor.BN(0), Buffer.from("MEMO"))
+    const preAccount = await program.account.traderState.fetch(traderStatePda);
+
+    await program.methods.executeTraderSwap(amountIn, new anchor.BN(0), new anchor.BN(0), Buffer.from("MEMO"))
         .accounts({
             authority: vaultOwner.publicKey,
             vault: vaultPda,
             traderState: traderStatePda,
             inputTokenAccount: traderQuoteAccount, // Quote
+            inputPosition: traderQuotePosition,
             outputTokenAccount: traderBaseAccount, // Base
             platformFeeAccount: adminQuoteAccount, // match input mint
             globalConfig: globalConfigPda,
@@ -257,8 +317,282 @@ describe("Phase 4: TraderState Accounting", () => {
         .rpc();
 
     const postAccount = await program.account.traderState.fetch(traderStatePda);
-    // Should be updated to 0 (amount_received)
-    // Start was 1,000,000.
-    assert.equal(postAccount.currentValue.toString(), "0");
+    // Previously overwritten with amount_received (0); cost-basis NAV keeps it
+    assert.equal(postAccount.currentValue.toString(), preAccount.currentValue.toString());
+  });
+
+  it("Rejects swap into a mint outside the vault whitelist", async () => {
+    const rogueMint = await createMint(provider.connection, payer, payer.publicKey, null, 6);
+    const rogueAccount = (await getOrCreateAssociatedTokenAccount(
+        provider.connection, payer, rogueMint, traderStatePda, true
+    )).address;
+    const [globalConfigPda] = anchor.web3.PublicKey.findProgramAddressSync([Buffer.from("global_config")], program.programId);
+
+    try {
+        await program.methods.executeTraderSwap(new anchor.BN(1_000), new anchor.BN(0), new anchor.BN(0), Buffer.from("MEMO"))
+            .accounts({
+                authority: vaultOwner.publicKey,
+                vault: vaultPda,
+                traderState: traderStatePda,
+                inputTokenAccount: traderBaseAccount,
+                outputTokenAccount: rogueAccount,
+                platformFeeAccount: ownerTokenAccount,
+                globalConfig: globalConfigPda,
+                jupiterProgram: MEMO_PROGRAM_ID,
+                tokenProgram: TOKEN_PROGRAM_ID,
+                instructions: anchor.web3.SYSVAR_INSTRUCTIONS_PUBKEY,
+            })
+            .signers([vaultOwner])
+            .rpc();
+        assert.fail("Should have failed TokenNotAllowed");
+    } catch (e) {
+        assert.include(e.message, "Token is not allowed in this vault");
+    }
+  });
+
+  it("Still sells a position whose mint was removed from the whitelist", async () => {
+    const [globalConfigPda] = anchor.web3.PublicKey.findProgramAddressSync([Buffer.from("global_config")], program.programId);
+    const adminQuoteAccount = await createAccount(
+        provider.connection, payer, quoteMint, payer.publicKey, anchor.web3.Keypair.generate()
+    );
+    await program.methods.removeAllowedMint(quoteMint)
+        .accounts({ owner: vaultOwner.publicKey, vault: vaultPda })
+        .signers([vaultOwner]).rpc();
+
+    // Quote -> Base unwinds the delisted holding
+    await program.methods.executeTraderSwap(new anchor.BN(1_000), new anchor.BN(0), new anchor.BN(0), Buffer.from("MEMO"))
+        .accounts({
+            authority: vaultOwner.publicKey,
+            vault: vaultPda,
+            traderState: traderStatePda,
+            inputTokenAccount: traderQuoteAccount,
+            inputPosition: traderQuotePosition,
+            outputTokenAccount: traderBaseAccount,
+            platformFeeAccount: adminQuoteAccount,
+            globalConfig: globalConfigPda,
+            jupiterProgram: MEMO_PROGRAM_ID,
+            tokenProgram: TOKEN_PROGRAM_ID,
+            instructions: anchor.web3.SYSVAR_INSTRUCTIONS_PUBKEY,
+        })
+        .signers([vaultOwner])
+        .rpc();
+
+    // Base -> Quote is still rejected
+    try {
+        await program.methods.executeTraderSwap(new anchor.BN(1_000), new anchor.BN(0), new anchor.BN(0), Buffer.from("MEMO"))
+            .accounts({
+                authority: vaultOwner.publicKey,
+                vault: vaultPda,
+                traderState: traderStatePda,
+                inputTokenAccount: traderBaseAccount,
+                outputTokenAccount: traderQuoteAccount,
+                outputPosition: traderQuotePosition,
+                platformFeeAccount: ownerTokenAccount,
+                globalConfig: globalConfigPda,
+                jupiterProgram: MEMO_PROGRAM_ID,
+                tokenProgram: TOKEN_PROGRAM_ID,
+                instructions: anchor.web3.SYSVAR_INSTRUCTIONS_PUBKEY,
+            })
+            .signers([vaultOwner])
+            .rpc();
+        assert.fail("Should have failed TokenNotAllowed");
+    } catch (e) {
+        assert.include(e.message, "Token is not allowed in this vault");
+    }
+
+    // Restore the whitelist for later tests
+    await program.methods.addAllowedMint(quoteMint)
+        .accounts({ owner: vaultOwner.publicKey, vault: vaultPda })
+        .signers([vaultOwner]).rpc();
+  });
+
+  it("Enforces owner risk limits on trade size and slippage", async () => {
+    const [globalConfigPda] = anchor.web3.PublicKey.findProgramAddressSync([Buffer.from("global_config")], program.programId);
+    const swapAccounts = {
+        authority: vaultOwner.publicKey,
+        vault: vaultPda,
+        traderState: traderStatePda,
+        inputTokenAccount: traderBaseAccount,
+        outputTokenAccount: traderQuoteAccount,
+        outputPosition: traderQuotePosition,
+        platformFeeAccount: ownerTokenAccount,
+        globalConfig: globalConfigPda,
+        jupiterProgram: MEMO_PROGRAM_ID,
+        tokenProgram: TOKEN_PROGRAM_ID,
+        instructions: anchor.web3.SYSVAR_INSTRUCTIONS_PUBKEY,
+    };
+
+    // 10% max trade, 1% max slippage
+    await program.methods.setRiskLimits(1_000, 100)
+        .accounts({ owner: vaultOwner.publicKey, traderState: traderStatePda })
+        .signers([vaultOwner]).rpc();
+
+    const account = await program.account.traderState.fetch(traderStatePda);
+    assert.equal(account.maxTradeBps, 1_000);
+    assert.equal(account.maxSlippageBps, 100);
+
+    const oversized = account.currentValue.addn(1);
+    try {
+        await program.methods.executeTraderSwap(oversized, new anchor.BN(0), new anchor.BN(0), Buffer.from("MEMO"))
+            .accounts(swapAccounts).signers([vaultOwner]).rpc();
+        assert.fail("Should have failed TradeSizeExceeded");
+    } catch (e) {
+        assert.include(e.message, "Trade size exceeds the TraderState maximum");
+    }
+
+    try {
+        // 5% below the quote with a 1% tolerance
+        await program.methods.executeTraderSwap(new anchor.BN(0), new anchor.BN(9_500), new anchor.BN(10_000), Buffer.from("MEMO"))
+            .accounts(swapAccounts).signers([vaultOwner]).rpc();
+        assert.fail("Should have failed SlippageLimitExceeded");
+    } catch (e) {
+        assert.include(e.message, "allows more slippage than the TraderState maximum");
+    }
+
+    try {
+        // No quote would put the slippage floor at zero
+        await program.methods.executeTraderSwap(new anchor.BN(0), new anchor.BN(0), new anchor.BN(0), Buffer.from("MEMO"))
+            .accounts(swapAccounts).signers([vaultOwner]).rpc();
+        assert.fail("Should have failed InvalidQuote");
+    } catch (e) {
+        assert.include(e.message, "requires a non-zero expected_amount_out");
+    }
+
+    try {
+        // min_amount_out above the quote
+        await program.methods.executeTraderSwap(new anchor.BN(0), new anchor.BN(10_001), new anchor.BN(10_000), Buffer.from("MEMO"))
+            .accounts(swapAccounts).signers([vaultOwner]).rpc();
+        assert.fail("Should have failed InvalidQuote");
+    } catch (e) {
+        assert.include(e.message, "requires a non-zero expected_amount_out");
+    }
+
+    // Clear limits so later suites are unaffected
+    await program.methods.setRiskLimits(0, 0)
+        .accounts({ owner: vaultOwner.publicKey, traderState: traderStatePda })
+        .signers([vaultOwner]).rpc();
+  });
+
+  it("Drawdown stop auto-pauses on a realized loss", async () => {
+    const [globalConfigPda] = anchor.web3.PublicKey.findProgramAddressSync([Buffer.from("global_config")], program.programId);
+
+    // 1 bps: platform fees alone have already drawn value below peak
+    await program.methods.setMaxDrawdown(1)
+        .accounts({ owner: vaultOwner.publicKey, traderState: traderStatePda })
+        .signers([vaultOwner]).rpc();
+
+    // The stop is checked when a swap lands in the Base Asset (Quote -> Base, mock fills nothing)
+    const adminQuoteAccount = await createAccount(
+        provider.connection, payer, quoteMint, payer.publicKey, anchor.web3.Keypair.generate()
+    );
+    await program.methods.executeTraderSwap(new anchor.BN(1_000), new anchor.BN(0), new anchor.BN(0), Buffer.from("MEMO"))
+        .accounts({
+            authority: vaultOwner.publicKey,
+            vault: vaultPda,
+            traderState: traderStatePda,
+            inputTokenAccount: traderQuoteAccount,
+            inputPosition: traderQuotePosition,
+            outputTokenAccount: traderBaseAccount,
+            platformFeeAccount: adminQuoteAccount,
+            globalConfig: globalConfigPda,
+            jupiterProgram: MEMO_PROGRAM_ID,
+            tokenProgram: TOKEN_PROGRAM_ID,
+            instructions: anchor.web3.SYSVAR_INSTRUCTIONS_PUBKEY,
+        })
+        .signers([vaultOwner])
+        .rpc();
+
+    const account = await program.account.traderState.fetch(traderStatePda);
+    assert.deepEqual(account.status, { paused: {} }, "Drawdown stop should pause the TraderState");
+    assert.isAbove(account.peakValue.toNumber(), account.currentValue.toNumber());
+
+    await program.methods.setMaxDrawdown(0)
+        .accounts({ owner: vaultOwner.publicKey, traderState: traderStatePda })
+        .signers([vaultOwner]).rpc();
+    await program.methods.resumeTraderState()
+        .accounts({ owner: vaultOwner.publicKey, traderState: traderStatePda })
+        .signers([vaultOwner]).rpc();
+  });
+
+  it("Rate limit caps swaps per window", async () => {
+    const [globalConfigPda] = anchor.web3.PublicKey.findProgramAddressSync([Buffer.from("global_config")], program.programId);
+    const before = await program.account.traderState.fetch(traderStatePda);
+    assert.isAbove(before.windowSwapCount, 0, "Earlier swaps count toward the window");
+
+    // Ceiling equal to swaps already executed: the next one must fail
+    await program.methods.setRateLimits(before.windowSwapCount, new anchor.BN(0))
+        .accounts({ owner: vaultOwner.publicKey, traderState: traderStatePda })
+        .signers([vaultOwner]).rpc();
+
+    try {
+        await program.methods.executeTraderSwap(new anchor.BN(1_000), new anchor.BN(0), new anchor.BN(0), Buffer.from("MEMO"))
+            .accounts({
+                authority: vaultOwner.publicKey,
+                vault: vaultPda,
+                traderState: traderStatePda,
+                inputTokenAccount: traderBaseAccount,
+                outputTokenAccount: traderQuoteAccount,
+                outputPosition: traderQuotePosition,
+                platformFeeAccount: ownerTokenAccount,
+                globalConfig: globalConfigPda,
+                jupiterProgram: MEMO_PROGRAM_ID,
+                tokenProgram: TOKEN_PROGRAM_ID,
+                instructions: anchor.web3.SYSVAR_INSTRUCTIONS_PUBKEY,
+            })
+            .signers([vaultOwner])
+            .rpc();
+        assert.fail("Should have failed SwapCountLimitExceeded");
+    } catch (e) {
+        assert.include(e.message, "swap count limit reached");
+    }
+
+    await program.methods.setRateLimits(0, new anchor.BN(0))
+        .accounts({ owner: vaultOwner.publicKey, traderState: traderStatePda })
+        .signers([vaultOwner]).rpc();
+  });
+
+  it("Trade journal keeps the most recent entries once it wraps", async () => {
+    const [globalConfigPda] = anchor.web3.PublicKey.findProgramAddressSync([Buffer.from("global_config")], program.programId);
+    const [tradeJournalPda] = anchor.web3.PublicKey.findProgramAddressSync(
+      [Buffer.from("trade_journal"), traderStatePda.toBuffer()],
+      program.programId
+    );
+    const CAPACITY = 64;
+    const SWAPS = CAPACITY + 6;
+    const totalBefore = (await program.account.tradeJournal.fetch(tradeJournalPda)).totalEntries.toNumber();
+
+    // Swap i is tagged by its fee: amount_in (i + 1) * 1_000 at 10 bps pays i + 1
+    for (let i = 0; i < SWAPS; i++) {
+        await program.methods.executeTraderSwap(new anchor.BN((i + 1) * 1_000), new anchor.BN(0), new anchor.BN(0), Buffer.from("MEMO"))
+            .accounts({
+                authority: vaultOwner.publicKey,
+                vault: vaultPda,
+                traderState: traderStatePda,
+                inputTokenAccount: traderBaseAccount,
+                outputTokenAccount: traderQuoteAccount,
+                outputPosition: traderQuotePosition,
+                platformFeeAccount: ownerTokenAccount,
+                globalConfig: globalConfigPda,
+                jupiterProgram: MEMO_PROGRAM_ID,
+                tokenProgram: TOKEN_PROGRAM_ID,
+                instructions: anchor.web3.SYSVAR_INSTRUCTIONS_PUBKEY,
+            })
+            .signers([vaultOwner])
+            .rpc();
+    }
+
+    const journal = await program.account.tradeJournal.fetch(tradeJournalPda);
+    const total = totalBefore + SWAPS;
+    assert.equal(journal.totalEntries.toNumber(), total);
+    assert.equal(journal.head, total % CAPACITY);
+
+    // Oldest first: the first SWAPS - CAPACITY swaps have been overwritten
+    let lastSlot = 0;
+    for (let i = 0; i < CAPACITY; i++) {
+        const entry = journal.entries[(journal.head + i) % CAPACITY];
+        assert.equal(entry.fee.toNumber(), SWAPS - CAPACITY + i + 1, `Entry ${i} out of order`);
+        assert.isAtLeast(entry.slot.toNumber(), lastSlot);
+        lastSlot = entry.slot.toNumber();
+    }
   });
 });
@@ -37,8 +37,12 @@ describe("Phase 5: Backend Swap Verification (Integration)", () => {
   let vaultPda: anchor.web3.PublicKey;
   let vaultTokenAccount: anchor.web3.PublicKey;
   let traderStatePda: anchor.web3.PublicKey;
-  let inputTokenAccount: anchor.web3.PublicKey;
-  let outputTokenAccount: anchor.web3.PublicKey;
+  let traderTokenAccount: anchor.web3.PublicKey;
+  let quoteMint: anchor.web3.PublicKey;
+  let traderQuoteAccount: anchor.web3.PublicKey;
+  let traderQuotePosition: anchor.web3.PublicKey;
+  let poolBaseAccount: anchor.web3.PublicKey;
+  let poolQuoteAccount: anchor.web3.PublicKey;
 
   const FUNDING_AMOUNT = new anchor.BN(1_000_000);
   const SWAP_AMOUNT_IN = new anchor.BN(100_000);
@@ -81,7 +85,7 @@ describe("Phase 5: Backend Swap Verification (Integration)", () => {
     // Check if initialized
     const gcInfo = await provider.connection.getAccountInfo(globalConfigPda);
     if (!gcInfo) {
-      await program.methods.initializeGlobalConfig()
+      await program.methods.initializeGlobalConfig(backendKeypair.publicKey)
         .accounts({
           globalConfig: globalConfigPda,
           admin: backendKeypair.publicKey,
@@ -92,6 +96,11 @@ describe("Phase 5: Backend Swap Verification (Integration)", () => {
       console.log("Global Config Initialized");
     }
 
+    // Swap program must be on the GlobalConfig allowlist (idempotent)
+    await program.methods.addSwapProgram(MOCK_SWAP_PROGRAM_ID).accounts({
+        globalConfig: globalConfigPda, admin: backendKeypair.publicKey
+    }).signers([backendKeypair]).rpc();
+
     // 4. Initialize UserVault
     [vaultPda] = anchor.web3.PublicKey.findProgramAddressSync(
       [Buffer.from("user_vault_v1"), backendKeypair.publicKey.toBuffer()],
@@ -134,21 +143,12 @@ describe("Phase 5: Backend Swap Verification (Integration)", () => {
       [Buffer.from("trader_state"), backendKeypair.publicKey.toBuffer(), trader.publicKey.toBuffer()],
       program.programId
     );
-    inputTokenAccount = getAssociatedTokenAddressSync(baseMint, traderStatePda, true);
-
-    // Create separate output token account (same mint) for mock_swap test
-    outputTokenAccount = await createAccount(
-      provider.connection,
-      backendKeypair,
-      baseMint,
-      traderStatePda,
-      anchor.web3.Keypair.generate()
-    );
+    traderTokenAccount = getAssociatedTokenAddressSync(baseMint, traderStatePda, true);
 
-    await program.methods.createTraderState(FUNDING_AMOUNT).accounts({
+    await program.methods.createTraderState(FUNDING_AMOUNT, 0).accounts({
       owner: backendKeypair.publicKey, trader: trader.publicKey, vault: vaultPda,
       traderState: traderStatePda, vaultTokenAccount: vaultTokenAccount,
-      traderTokenAccount: inputTokenAccount, mint: baseMint,
+      traderTokenAccount: traderTokenAccount, mint: baseMint,
       systemProgram: anchor.web3.SystemProgram.programId,
       tokenProgram: TOKEN_PROGRAM_ID, associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID
     }).signers([backendKeypair]).rpc();
@@ -158,6 +158,29 @@ describe("Phase 5: Backend Swap Verification (Integration)", () => {
       signer: backendKeypair.publicKey, vault: vaultPda, traderState: traderStatePda,
     }).signers([backendKeypair]).rpc();
 
+    // 8. Quote position and the mock pool (owned by the backend) that fills the swap
+    quoteMint = await createMint(provider.connection, backendKeypair, backendKeypair.publicKey, null, 6);
+    await program.methods.addAllowedMint(quoteMint)
+      .accounts({ owner: backendKeypair.publicKey, vault: vaultPda })
+      .signers([backendKeypair]).rpc();
+    traderQuoteAccount = getAssociatedTokenAddressSync(quoteMint, traderStatePda, true);
+    [traderQuotePosition] = anchor.web3.PublicKey.findProgramAddressSync(
+      [Buffer.from("trader_position"), traderStatePda.toBuffer(), quoteMint.toBuffer()],
+      program.programId
+    );
+    await program.methods.createTraderAta().accounts({
+      owner: backendKeypair.publicKey, vault: vaultPda, traderState: traderStatePda, mint: quoteMint,
+      traderTokenAccount: traderQuoteAccount, traderPosition: traderQuotePosition,
+      tokenProgram: TOKEN_PROGRAM_ID, associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+      systemProgram: anchor.web3.SystemProgram.programId
+    }).signers([backendKeypair]).rpc();
+
+    poolBaseAccount = await createAccount(
+      provider.connection, backendKeypair, baseMint, backendKeypair.publicKey, anchor.web3.Keypair.generate()
+    );
+    poolQuoteAccount = await createAccount(provider.connection, backendKeypair, quoteMint, backendKeypair.publicKey);
+    await mintTo(provider.connection, backendKeypair, quoteMint, poolQuoteAccount, backendKeypair.publicKey, 1_000_000);
+
     console.log("Setup Complete");
   });
 
@@ -167,85 +190,44 @@ describe("Phase 5: Backend Swap Verification (Integration)", () => {
       program.programId
     );
 
-    const balanceInBefore = (await getAccount(provider.connection, inputTokenAccount)).amount;
-    const balanceOutBefore = (await getAccount(provider.connection, outputTokenAccount)).amount;
+    const balanceInBefore = (await getAccount(provider.connection, traderTokenAccount)).amount;
+    const balanceOutBefore = (await getAccount(provider.connection, traderQuoteAccount)).amount;
     const feeBalanceBefore = (await getAccount(provider.connection, platformFeeAccount)).amount;
 
     console.log("Balance In Before:", balanceInBefore.toString());
     console.log("Balance Out Before:", balanceOutBefore.toString());
 
-    // Mock Swap Data (passed to execute_trader_swap)
-    // stellalpha_vault passes this data through to the CPI call
-    // The mock_swap program expects (amount_in, min_amount_out)
-    // But wait, execute_trader_swap takes (amount_in, min_amount_out, data)
-    // And it passes 'data' to the CPI.
-    // The mock_swap instruction `swap` arguments are (amount_in, min_amount_out).
-    // The `data` buffer passed to CPI must match the selector + args of mock_swap::swap.
-    // However, stellalpha_vault passes `data` as the instruction data.
-
-    // Wait, let's verify how stellalpha_vault constructs the CPI instruction.
-    // Line 637: `data: data,`
-
-    // The `data` argument to `execute_trader_swap` MUST contain the discriminator and arguments for `mock_swap:swap`.
-    // In `scripts/backend_swap.ts`, we constructed this manually.
-
-    // IDL for mock_swap:
-    // swap(amount_in: u64, min_amount_out: u64)
-    // Discriminator is sha256("global:swap")[..8]
-
-    // Let's rely on Anchor to construct the data, or construct manually.
-    // Creating a dummy program instance to encode the instruction data is easiest if possible,
-    // or just manual encoding.
-
-    // Manual encoding:
-    // Discriminator: "global:swap" -> sighash
-    // u64 amount_in
-    // u64 min_amount_out
-
-    // But wait, `execute_trader_swap` arguments are (amount_in, min_amount_out, data).
-    // The `amount_in` and `min_amount_out` passed to `execute_trader_swap` are used by stellalpha_vault for checks.
-    // The `data` is passed BLINDLY to the CPI.
-    // So `data` must encode the call to `mock_swap::swap(amount_in, min_amount_out)`.
-
-    const hasher = anchor.utils.sha256.hash("global:swap");
-    const sighash = Buffer.from(hasher.slice(0, 16), "hex"); // anchor 0.29+ utils might vary, check sighash logic
-    // Actually the sighash is first 8 bytes of sha256("global:swap")
-
-    // Let's use the actual mock_swap program interface to encode if we can.
-    // Or just reproduce the logic from `backend_swap.ts`:
-    // const mockSwapData = Buffer.concat([
-    //   Buffer.from("248c69e917587c86", "hex"), // global:swap discriminator
-    //   AMOUNT_IN.toBuffer("le", 8),
-    //   MIN_AMOUNT_OUT.toBuffer("le", 8),
-    // ]);
-
-    // I need the discriminator. "global:swap"
-    const discriminator = anchor.utils.sha256.hash("global:swap").slice(0, 16);
-    // Wait, hash returns hex string. slice(0, 16) gives 16 hex chars = 8 bytes.
-    // "global:swap" -> 248c69e917587c86...
-
+    // The `data` argument to `execute_trader_swap` is passed BLINDLY to the CPI,
+    // so it must encode the call to `mock_swap::swap_pair(amount_in, amount_out)`.
+    // `amount_in` and `min_amount_out` passed to `execute_trader_swap` are what
+    // stellalpha_vault itself checks.
     const mockSwapIdl = JSON.parse(fs.readFileSync("./target/idl/mock_swap.json", "utf8"));
-    const MOCK_ACTUAL_INPUT = SWAP_AMOUNT_IN.sub(new anchor.BN(100)); // 99,900
+    const MOCK_ACTUAL_INPUT = SWAP_AMOUNT_IN.sub(new anchor.BN(100)); // 99,900 after the fee
+    const MOCK_OUTPUT = MOCK_ACTUAL_INPUT.muln(95).divn(100); // 94,905 at a 95% rate
     const mockSwapCoder = new anchor.BorshInstructionCoder(mockSwapIdl);
-    const mockSwapData = mockSwapCoder.encode("swap", {
+    const mockSwapData = mockSwapCoder.encode("swap_pair", {
       amount_in: MOCK_ACTUAL_INPUT,
-      min_amount_out: MIN_AMOUNT_OUT
+      amount_out: MOCK_OUTPUT
     });
 
     const remainingAccounts = [
       { pubkey: traderStatePda, isWritable: false, isSigner: false }, // authority (signer via invoke_signed)
-      { pubkey: inputTokenAccount, isWritable: true, isSigner: false },
-      { pubkey: outputTokenAccount, isWritable: true, isSigner: false },
+      { pubkey: traderTokenAccount, isWritable: true, isSigner: false }, // input (Base)
+      { pubkey: traderQuoteAccount, isWritable: true, isSigner: false }, // output (Quote)
+      { pubkey: backendKeypair.publicKey, isWritable: false, isSigner: true }, // pool authority
+      { pubkey: poolBaseAccount, isWritable: true, isSigner: false },
+      { pubkey: poolQuoteAccount, isWritable: true, isSigner: false },
       { pubkey: TOKEN_PROGRAM_ID, isWritable: false, isSigner: false },
     ];
 
-    await program.methods.executeTraderSwap(SWAP_AMOUNT_IN, MIN_AMOUNT_OUT, mockSwapData)
+    await program.methods.executeTraderSwap(SWAP_AMOUNT_IN, MIN_AMOUNT_OUT, MIN_AMOUNT_OUT, mockSwapData)
       .accounts({
         authority: backendKeypair.publicKey,
         vault: vaultPda,
         traderState: traderStatePda,
-        inputTokenAccount: inputTokenAccount,
-        outputTokenAccount: outputTokenAccount,
+        inputTokenAccount: traderTokenAccount,
+        outputTokenAccount: traderQuoteAccount,
+        outputPosition: traderQuotePosition,
         platformFeeAccount: platformFeeAccount,
         globalConfig: globalConfigPda,
         jupiterProgram: MOCK_SWAP_PROGRAM_ID,
@@ -258,42 +240,23 @@ describe("Phase 5: Backend Swap Verification (Integration)", () => {
 
     console.log("Swap Executed");
 
-    const balanceInAfter = (await getAccount(provider.connection, inputTokenAccount)).amount;
-    const balanceOutAfter = (await getAccount(provider.connection, outputTokenAccount)).amount;
+    const balanceInAfter = (await getAccount(provider.connection, traderTokenAccount)).amount;
+    const balanceOutAfter = (await getAccount(provider.connection, traderQuoteAccount)).amount;
     const feeBalanceAfter = (await getAccount(provider.connection, platformFeeAccount)).amount;
 
     // Verify Fee (10 bps of 100,000 = 100)
     const expectedFee = BigInt(100);
     assert.equal(feeBalanceAfter - feeBalanceBefore, expectedFee, "Fee should be paid");
 
-    // Verify Output (95% of 99,900 = 94,905)
-    // Input used = 100,000
-    // Fee = 100
-    // Swap Input = 99,900
-    // Return = 99,900 * 0.95 = 94,905
-
-    const expectedOutputDelta = BigInt(94905);
-    assert.equal(balanceOutAfter - balanceOutBefore, expectedOutputDelta, "Output should match mock 95% logic");
-
-    // Verify Input Decrease
-    // Should be exactly SWAP_AMOUNT_IN (100,000)
-    // Because mock_swap doesn't burn/transfer from input?
-    // Wait, mock_swap DOES transfer from input to output.
-    // So input should decrease by SWAP_AMOUNT_IN?
-    // In `backend_swap.ts` we saw:
-    // Input ATA: 500,000 -> 404,995 (-95,005)
-    // Wait. 
-    // Fee = 100.
-    // Transfer (Swap) = 94,905.
-    // Total out of Input = 100 + 94,905 = 95,005.
-    // But SWAP_AMOUNT_IN passed to `execute_trader_swap` is 100,000.
-    // The `stellalpha_vault` verifies `amount_spent`.
-
-    // amount_spent = balance_in_before - balance_in_after
-    // amount_spent <= swap_amount_in is the restriction.
-    // Here amount_spent = 95,005 which is <= 100,000. So it passes.
-
-    const expectedInputDecrease = BigInt(95005);
-    assert.equal(balanceInBefore - balanceInAfter, expectedInputDecrease, "Input balance reflection");
+    // Input decreases by fee + swap = 100,000; output receives the 95% fill
+    assert.equal(balanceInBefore - balanceInAfter, BigInt(SWAP_AMOUNT_IN.toString()), "Input balance reflection");
+    assert.equal(balanceOutAfter - balanceOutBefore, BigInt(MOCK_OUTPUT.toString()), "Output should match mock 95% logic");
+
+    // Base -> Quote at cost: only the platform fee is realized
+    const ts = await program.account.traderState.fetch(traderStatePda);
+    assert.equal(ts.currentValue.toString(), FUNDING_AMOUNT.subn(100).toString(), "NAV net of fee");
+    assert.equal(ts.openPositions, 1, "Quote position opened");
+    const position = await program.account.traderPosition.fetch(traderQuotePosition);
+    assert.equal(position.costBasis.toString(), MOCK_ACTUAL_INPUT.toString());
   });
 });
@@ -33,6 +33,7 @@ describe("Phase 5: Settlement & Withdrawals", () => {
   let vaultTokenAccount: anchor.web3.PublicKey;
   let traderStatePda: anchor.web3.PublicKey;
   let traderTokenAccount: anchor.web3.PublicKey;
+  let adminFeeAccount: anchor.web3.PublicKey;
 
   const FUNDING_AMOUNT = new anchor.BN(1_000_000); 
 
@@ -67,11 +68,16 @@ describe("Phase 5: Settlement & Withdrawals", () => {
     // Config & Vault
     const [globalConfigPda] = anchor.web3.PublicKey.findProgramAddressSync([Buffer.from("global_config")], program.programId);
     try {
-        await program.methods.initializeGlobalConfig().accounts({
+        await program.methods.initializeGlobalConfig(payer.publicKey).accounts({
             globalConfig: globalConfigPda, admin: payer.publicKey, systemProgram: anchor.web3.SystemProgram.programId
         }).signers([payer]).rpc();
     } catch(e) {}
 
+    // Performance fee destination: admin (payer) Base Asset ATA
+    adminFeeAccount = (await getOrCreateAssociatedTokenAccount(
+        provider.connection, payer, baseMint, payer.publicKey
+    )).address;
+
     [vaultPda] = anchor.web3.PublicKey.findProgramAddressSync(
         [Buffer.from("user_vault_v1"), vaultOwner.publicKey.toBuffer()],
         program.programId
@@ -101,7 +107,7 @@ describe("Phase 5: Settlement & Withdrawals", () => {
     );
     traderTokenAccount = getAssociatedTokenAddressSync(baseMint, traderStatePda, true);
 
-    await program.methods.createTraderState(FUNDING_AMOUNT).accounts({
+    await program.methods.createTraderState(FUNDING_AMOUNT, 0).accounts({
         owner: vaultOwner.publicKey, trader: trader.publicKey, vault: vaultPda,
         traderState: traderStatePda, vaultTokenAccount: vaultTokenAccount,
         traderTokenAccount: traderTokenAccount, mint: baseMint,
@@ -111,13 +117,30 @@ describe("Phase 5: Settlement & Withdrawals", () => {
     console.log("TraderState Created:", traderStatePda.toBase58());
   });
 
+  it("Rejects Crystallization Before Minimum Interval", async () => {
+    // Vault authority is the owner in this suite; last_crystallized_at = creation time.
+    try {
+        await program.methods.crystallizePerformanceFee().accounts({
+            authority: vaultOwner.publicKey,
+            vault: vaultPda,
+            traderState: traderStatePda,
+            traderTokenAccount: traderTokenAccount,
+            performanceFeeAccount: adminFeeAccount
+        }).signers([vaultOwner]).rpc();
+        assert.fail("Should have failed CrystallizationTooSoon");
+    } catch (e) {
+        assert.include(e.message, "Minimum interval between performance fee crystallizations");
+    }
+  });
+
   it("Fails to Settle if Not Paused", async () => {
     try {
         await program.methods.settleTraderState().accounts({
             owner: vaultOwner.publicKey, // Renamed from authority
             vault: vaultPda,
             traderState: traderStatePda,
-            traderTokenAccount: traderTokenAccount
+            traderTokenAccount: traderTokenAccount,
+            performanceFeeAccount: adminFeeAccount
         }).signers([vaultOwner]).rpc();
         assert.fail("Should have failed");
     } catch (e) {
@@ -136,11 +159,138 @@ describe("Phase 5: Settlement & Withdrawals", () => {
         owner: vaultOwner.publicKey,
         vault: vaultPda,
         traderState: traderStatePda,
-        traderTokenAccount: traderTokenAccount
+        traderTokenAccount: traderTokenAccount,
+        performanceFeeAccount: adminFeeAccount
     }).signers([vaultOwner]).rpc();
     
     const account = await program.account.traderState.fetch(traderStatePda);
-    assert.isTrue(account.isSettled);
+    assert.deepEqual(account.status, { settled: {} });
+  });
+
+  it("Charges Performance Fee Above High-Water Mark", async () => {
+    const trader3 = anchor.web3.Keypair.generate();
+    const [ts3] = anchor.web3.PublicKey.findProgramAddressSync(
+        [Buffer.from("trader_state"), vaultOwner.publicKey.toBuffer(), trader3.publicKey.toBuffer()],
+        program.programId
+    );
+    const ta3 = getAssociatedTokenAddressSync(baseMint, ts3, true);
+
+    await program.methods.createTraderState(FUNDING_AMOUNT, 0).accounts({
+        owner: vaultOwner.publicKey, trader: trader3.publicKey, vault: vaultPda,
+        traderState: ts3, vaultTokenAccount: vaultTokenAccount,
+        traderTokenAccount: ta3, mint: baseMint,
+        systemProgram: anchor.web3.SystemProgram.programId,
+        tokenProgram: TOKEN_PROGRAM_ID, associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID
+    }).signers([vaultOwner]).rpc();
+
+    // Simulate trading profit: 500_000 above the 1_000_000 HWM
+    const PROFIT = 500_000;
+    await mintTo(provider.connection, payer, baseMint, ta3, payer.publicKey, PROFIT);
+
+    await program.methods.pauseTraderState().accounts({
+        owner: vaultOwner.publicKey, traderState: ts3
+    }).signers([vaultOwner]).rpc();
+
+    const feeBefore = (await getAccount(provider.connection, adminFeeAccount)).amount;
+
+    await program.methods.settleTraderState().accounts({
+        owner: vaultOwner.publicKey,
+        vault: vaultPda,
+        traderState: ts3,
+        traderTokenAccount: ta3,
+        performanceFeeAccount: adminFeeAccount
+    }).signers([vaultOwner]).rpc();
+
+    const feeAfter = (await getAccount(provider.connection, adminFeeAccount)).amount;
+    const config = await program.account.globalConfig.fetch(
+        anchor.web3.PublicKey.findProgramAddressSync([Buffer.from("global_config")], program.programId)[0]
+    );
+    const expectedFee = BigInt(Math.floor(PROFIT * config.performanceFeeBps / 10000));
+    assert.equal(feeAfter - feeBefore, expectedFee, "Performance fee should be paid to admin");
+
+    const account = await program.account.traderState.fetch(ts3);
+    const expectedEquity = FUNDING_AMOUNT.toNumber() + PROFIT - Number(expectedFee);
+    assert.equal(account.highWaterMark.toNumber(), expectedEquity, "HWM advances to post-fee equity");
+    assert.equal(account.currentValue.toNumber(), expectedEquity);
+    assert.equal(account.cumulativeProfit.toNumber(), PROFIT - Number(expectedFee));
+    assert.deepEqual(account.status, { settled: {} });
+  });
+
+  it("Crystallizes the Performance Fee After the Minimum Interval", async function () {
+    // Reachable only in builds with `--features short-crystallization-interval`
+    const interval = Number(program.idl.constants
+        ?.find((c) => c.name.replace(/_/g, "").toLowerCase() === "mincrystallizationinterval")
+        ?.value);
+    if (!(interval <= 60)) {
+        this.skip();
+    }
+
+    const trader4 = anchor.web3.Keypair.generate();
+    const [ts4] = anchor.web3.PublicKey.findProgramAddressSync(
+        [Buffer.from("trader_state"), vaultOwner.publicKey.toBuffer(), trader4.publicKey.toBuffer()],
+        program.programId
+    );
+    const ta4 = getAssociatedTokenAddressSync(baseMint, ts4, true);
+
+    await program.methods.createTraderState(FUNDING_AMOUNT, 0).accounts({
+        owner: vaultOwner.publicKey, trader: trader4.publicKey, vault: vaultPda,
+        traderState: ts4, vaultTokenAccount: vaultTokenAccount,
+        traderTokenAccount: ta4, mint: baseMint,
+        systemProgram: anchor.web3.SystemProgram.programId,
+        tokenProgram: TOKEN_PROGRAM_ID, associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID
+    }).signers([vaultOwner]).rpc();
+
+    const PROFIT = 300_000;
+    await mintTo(provider.connection, payer, baseMint, ta4, payer.publicKey, PROFIT);
+
+    // Wait for the validator clock to pass the interval
+    const before = await program.account.traderState.fetch(ts4);
+    const due = before.lastCrystallizedAt.toNumber() + interval;
+    while ((await provider.connection.getBlockTime(await provider.connection.getSlot())) < due) {
+        await new Promise((resolve) => setTimeout(resolve, 500));
+    }
+
+    const feeBefore = (await getAccount(provider.connection, adminFeeAccount)).amount;
+    await program.methods.crystallizePerformanceFee().accounts({
+        authority: vaultOwner.publicKey,
+        vault: vaultPda,
+        traderState: ts4,
+        traderTokenAccount: ta4,
+        performanceFeeAccount: adminFeeAccount
+    }).signers([vaultOwner]).rpc();
+
+    const config = await program.account.globalConfig.fetch(
+        anchor.web3.PublicKey.findProgramAddressSync([Buffer.from("global_config")], program.programId)[0]
+    );
+    const expectedFee = Math.floor(PROFIT * config.performanceFeeBps / 10000);
+    const feeAfter = (await getAccount(provider.connection, adminFeeAccount)).amount;
+    assert.equal((feeAfter - feeBefore).toString(), expectedFee.toString(), "Performance fee should be paid to admin");
+    assert.equal(
+        (await getAccount(provider.connection, ta4)).amount.toString(),
+        (FUNDING_AMOUNT.toNumber() + PROFIT - expectedFee).toString()
+    );
+
+    const account = await program.account.traderState.fetch(ts4);
+    const expectedEquity = FUNDING_AMOUNT.toNumber() + PROFIT - expectedFee;
+    assert.equal(account.highWaterMark.toNumber(), expectedEquity, "HWM advances to post-fee equity");
+    assert.equal(account.currentValue.toNumber(), expectedEquity);
+    assert.equal(account.cumulativeProfit.toNumber(), PROFIT - expectedFee);
+    assert.isAtLeast(account.lastCrystallizedAt.toNumber(), due);
+    assert.deepEqual(account.status, { created: {} }, "The allocation stays open");
+
+    // The interval restarts from this crystallization
+    try {
+        await program.methods.crystallizePerformanceFee().accounts({
+            authority: vaultOwner.publicKey,
+            vault: vaultPda,
+            traderState: ts4,
+            traderTokenAccount: ta4,
+            performanceFeeAccount: adminFeeAccount
+        }).signers([vaultOwner]).rpc();
+        assert.fail("Should have failed CrystallizationTooSoon");
+    } catch (e) {
+        assert.include(e.message, "Minimum interval between performance fee crystallizations");
+    }
   });
 
   it("Fails to Withdraw if Not Settled", async () => {
@@ -156,7 +306,7 @@ describe("Phase 5: Settlement & Withdrawals", () => {
     );
     const ta2 = getAssociatedTokenAddressSync(baseMint, ts2, true);
     
-    await program.methods.createTraderState(FUNDING_AMOUNT).accounts({
+    await program.methods.createTraderState(FUNDING_AMOUNT, 0).accounts({
         owner: vaultOwner.publicKey, trader: trader2.publicKey, vault: vaultPda,
         traderState: ts2, vaultTokenAccount: vaultTokenAccount,
         traderTokenAccount: ta2, mint: baseMint,
@@ -213,6 +363,11 @@ describe("Phase 5: Settlement & Withdrawals", () => {
     } catch (e) {
         assert.include(e.message, "Account does not exist");
     }
+    const [tradeJournalPda] = anchor.web3.PublicKey.findProgramAddressSync(
+        [Buffer.from("trade_journal"), traderStatePda.toBuffer()],
+        program.programId
+    );
+    assert.isNull(await provider.connection.getAccountInfo(tradeJournalPda), "TradeJournal should be closed");
   });
 
 });
@@ -1,6 +1,7 @@
 import * as anchor from "@coral-xyz/anchor";
 import { Program } from "@coral-xyz/anchor";
 import { StellalphaVault } from "../target/types/stellalpha_vault";
+import { MockSwap } from "../target/types/mock_swap";
 import { assert } from "chai";
 import { 
   createMint, 
@@ -13,6 +14,7 @@ import {
 } from "@solana/spl-token";
 import * as fs from "fs";
 import * as os from "os";
+import { logEvents } from "./utils/events";
 
 describe("Phase 6: Migration & Cleanup", () => {
   const provider = anchor.AnchorProvider.env();
@@ -56,7 +58,7 @@ describe("Phase 6: Migration & Cleanup", () => {
         program.programId
     );
     try {
-        await program.methods.initializeGlobalConfig().accounts({
+        await program.methods.initializeGlobalConfig(payer.publicKey).accounts({
             globalConfig: globalConfigPda, 
             admin: payer.publicKey, 
             systemProgram: anchor.web3.SystemProgram.programId
@@ -145,6 +147,38 @@ describe("Phase 6: Migration & Cleanup", () => {
     console.log("✅ Legacy trading toggled to:", config.legacyTradingEnabled);
   });
 
+  // ========================================================================
+  // Test 2b: The guardian halt also stops the legacy path
+  // ========================================================================
+  it("Legacy execute_swap fails while trading is halted", async () => {
+    await program.methods.haltTrading().accounts({
+        globalConfig: globalConfigPda,
+        signer: payer.publicKey
+    }).signers([payer]).rpc();
+
+    try {
+        await program.methods.executeSwap(new anchor.BN(1_000), new anchor.BN(0)).accounts({
+            vault: vaultPda,
+            tokenAccountIn: vaultTokenAccount,
+            tokenAccountOut: vaultTokenAccount,
+            platformFeeAccount: vaultTokenAccount,
+            authority: payer.publicKey,
+            globalConfig: globalConfigPda,
+            sysvarInstructions: anchor.web3.SYSVAR_INSTRUCTIONS_PUBKEY,
+            jupiterProgram: TOKEN_PROGRAM_ID,
+            tokenProgram: TOKEN_PROGRAM_ID
+        }).signers([payer]).rpc();
+        assert.fail("Legacy swap should be rejected while halted");
+    } catch (e) {
+        assert.include(e.message, "Protocol trading is halted");
+    } finally {
+        await program.methods.resumeTrading().accounts({
+            globalConfig: globalConfigPda,
+            admin: payer.publicKey
+        }).signers([payer]).rpc();
+    }
+  });
+
   // ========================================================================
   // Test 3: Admin can toggle back to disabled
   // ========================================================================
@@ -234,12 +268,19 @@ describe("Phase 6: Migration & Cleanup", () => {
     assert.equal(vaultBalAfter.toString(), "0", "Vault should be empty");
     
     // Now close the ATA
-    await program.methods.closeVaultAta().accounts({
+    const closeSig = await program.methods.closeVaultAta().accounts({
         vault: vaultPda,
         owner: vaultOwner.publicKey,
         vaultTokenAccount: vaultTokenAccount,
         tokenProgram: TOKEN_PROGRAM_ID
-    }).signers([vaultOwner]).rpc();
+    }).signers([vaultOwner]).rpc({ commitment: "confirmed" });
+
+    const closeTx = await provider.connection.getTransaction(closeSig, { commitment: "confirmed", maxSupportedTransactionVersion: 0 });
+    const closedEvent = logEvents(program, closeTx).find((e) => e.name === "vaultAtaClosed");
+    assert.ok(closedEvent, "VaultAtaClosed should be emitted");
+    assert.ok(closedEvent.data.vault.equals(vaultPda));
+    assert.ok(closedEvent.data.mint.equals(baseMint));
+    assert.ok(closedEvent.data.tokenAccount.equals(vaultTokenAccount));
     
     // Verify ATA is closed
     try {
@@ -266,7 +307,7 @@ describe("Phase 6: Migration & Cleanup", () => {
     // We'll just create a TraderState and verify it works
     
     // First re-init vault ATA since we closed it
-    await program.methods.initVaultAta().accounts({
+    const initSig = await program.methods.initVaultAta().accounts({
         vault: vaultPda, 
         owner: vaultOwner.publicKey, 
         mint: baseMint,
@@ -274,7 +315,13 @@ describe("Phase 6: Migration & Cleanup", () => {
         tokenProgram: TOKEN_PROGRAM_ID,
         associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID, 
         systemProgram: anchor.web3.SystemProgram.programId
-    }).signers([vaultOwner]).rpc();
+    }).signers([vaultOwner]).rpc({ commitment: "confirmed" });
+
+    const initTx = await provider.connection.getTransaction(initSig, { commitment: "confirmed", maxSupportedTransactionVersion: 0 });
+    const initEvent = logEvents(program, initTx).find((e) => e.name === "vaultAtaInitialized");
+    assert.ok(initEvent, "VaultAtaInitialized should be emitted");
+    assert.ok(initEvent.data.vault.equals(vaultPda));
+    assert.ok(initEvent.data.tokenAccount.equals(vaultTokenAccount));
     
     // Fund vault again
     const ownerAtaInfo = await getOrCreateAssociatedTokenAccount(
@@ -300,7 +347,7 @@ describe("Phase 6: Migration & Cleanup", () => {
     const traderTokenAccount = getAssociatedTokenAddressSync(baseMint, traderStatePda, true);
     
     const FUNDING = new anchor.BN(1_000_000);
-    await program.methods.createTraderState(FUNDING).accounts({
+    await program.methods.createTraderState(FUNDING, 0).accounts({
         owner: vaultOwner.publicKey, 
         trader: trader.publicKey, 
         vault: vaultPda,
@@ -318,4 +365,257 @@ describe("Phase 6: Migration & Cleanup", () => {
     console.log("✅ TraderState created successfully. Legacy flag has no effect on TraderState operations.");
   });
 
+  // ========================================================================
+  // Test 8: A TraderState in the original 132-byte layout migrates in place
+  // ========================================================================
+  it("Migrates a legacy 132-byte TraderState", async () => {
+    // Preloaded by Anchor.toml: paused + initialized, current_value 1_000_000,
+    // high_water_mark 1_200_000, cumulative_profit -200_000. Its vault and
+    // ATAs are created here.
+    const legacyOwner = anchor.web3.Keypair.fromSecretKey(Uint8Array.from(
+        JSON.parse(fs.readFileSync("./tests/fixtures/legacy_owner.json", "utf-8"))
+    ));
+    const legacyTraderState = new anchor.web3.PublicKey("5226iAa78yMJMVvxSf6LimfmtMDxFJSbCxqq5cCw7Vk2");
+    const legacyTrader = new anchor.web3.PublicKey("2CCekQVM3KAzZ6d6uYRKCkC8HDX7a8xNTPiFTCX5VLsL");
+    const [legacyVault] = anchor.web3.PublicKey.findProgramAddressSync(
+        [Buffer.from("user_vault_v1"), legacyOwner.publicKey.toBuffer()],
+        program.programId
+    );
+
+    const before = await provider.connection.getAccountInfo(legacyTraderState);
+    assert.equal(before.data.length, 132, "Fixture should use the legacy layout");
+
+    const transferTx = new anchor.web3.Transaction().add(
+        anchor.web3.SystemProgram.transfer({
+            fromPubkey: payer.publicKey,
+            toPubkey: legacyOwner.publicKey,
+            lamports: 100_000_000
+        })
+    );
+    await anchor.web3.sendAndConfirmTransaction(provider.connection, transferTx, [payer]);
+
+    await program.methods.initializeVault(legacyOwner.publicKey, baseMint)
+        .accounts({ vault: legacyVault, owner: legacyOwner.publicKey, systemProgram: anchor.web3.SystemProgram.programId })
+        .signers([legacyOwner]).rpc();
+
+    // Legacy ATAs were created without a TraderPosition
+    const legacyAltMint = await createMint(provider.connection, payer, payer.publicKey, null, 6);
+    const legacyAltAta = (await getOrCreateAssociatedTokenAccount(
+        provider.connection, payer, legacyAltMint, legacyTraderState, true
+    )).address;
+    await mintTo(provider.connection, payer, legacyAltMint, legacyAltAta, payer.publicKey, 5_000);
+    const legacyBaseAta = (await getOrCreateAssociatedTokenAccount(
+        provider.connection, payer, baseMint, legacyTraderState, true
+    )).address;
+    // Legacy current_value (1_000_000) still counts what the alt holding cost
+    await mintTo(provider.connection, payer, baseMint, legacyBaseAta, payer.publicKey, 400_000);
+    const migrateAccounts = {
+        owner: legacyOwner.publicKey, traderState: legacyTraderState, traderTokenAccount: legacyBaseAta
+    };
+    const positionPda = (mint: anchor.web3.PublicKey) => anchor.web3.PublicKey.findProgramAddressSync(
+        [Buffer.from("trader_position"), legacyTraderState.toBuffer(), mint.toBuffer()],
+        program.programId
+    )[0];
+    const pair = (mint: anchor.web3.PublicKey, ata: anchor.web3.PublicKey) => [
+        { pubkey: positionPda(mint), isWritable: true, isSigner: false },
+        { pubkey: ata, isWritable: false, isSigner: false },
+    ];
+
+    try {
+        await program.methods.migrateTraderState()
+            .accounts(migrateAccounts)
+            .remainingAccounts(pair(baseMint, legacyBaseAta))
+            .signers([legacyOwner]).rpc();
+        assert.fail("The Base Asset ATA cannot be registered as a position");
+    } catch (e) {
+        assert.include(e.message, "cannot have a TraderPosition");
+    }
+
+    try {
+        await program.methods.migrateTraderState()
+            .accounts(migrateAccounts)
+            .remainingAccounts([...pair(legacyAltMint, legacyAltAta), ...pair(legacyAltMint, legacyAltAta)])
+            .signers([legacyOwner]).rpc();
+        assert.fail("An ATA cannot be registered twice");
+    } catch (e) {
+        assert.include(e.message, "MigrationAccountsMismatch");
+    }
+
+    const sig = await program.methods.migrateTraderState()
+        .accounts(migrateAccounts)
+        .remainingAccounts(pair(legacyAltMint, legacyAltAta))
+        .signers([legacyOwner]).rpc({ commitment: "confirmed" });
+
+    const tx = await provider.connection.getTransaction(sig, { commitment: "confirmed", maxSupportedTransactionVersion: 0 });
+    const migratedEvent = logEvents(program, tx).find((e) => e.name === "traderStateMigrated");
+    assert.ok(migratedEvent, "TraderStateMigrated should be emitted");
+    assert.ok(migratedEvent.data.traderState.equals(legacyTraderState));
+    assert.ok(migratedEvent.data.owner.equals(legacyOwner.publicKey));
+    assert.deepEqual(migratedEvent.data.status, { paused: {} });
+    assert.equal(migratedEvent.data.positionCount, 1);
+
+    const after = await provider.connection.getAccountInfo(legacyTraderState);
+    assert.isAbove(after.data.length, 132, "Account should be resized to the current layout");
+    assert.isAtLeast(after.lamports, await provider.connection.getMinimumBalanceForRentExemption(after.data.length));
+
+    const ts = await program.account.traderState.fetch(legacyTraderState);
+    assert.ok(ts.owner.equals(legacyOwner.publicKey));
+    assert.ok(ts.trader.equals(legacyTrader));
+    assert.ok(ts.vault.equals(legacyVault));
+    assert.deepEqual(ts.status, { paused: {} });
+    assert.deepEqual(ts.resumeStatus, { active: {} }, "Initialized allocations resume as Active");
+    assert.equal(ts.currentValue.toString(), "1000000");
+    assert.equal(ts.highWaterMark.toString(), "1200000");
+    assert.equal(ts.cumulativeProfit.toString(), "-200000");
+    assert.equal(ts.peakValue.toString(), "1000000", "Peak starts at current_value");
+    assert.equal(ts.allocationId, 0);
+    assert.isAbove(ts.lastCrystallizedAt.toNumber(), 0);
+    assert.equal(ts.maxTradeBps, 0);
+    assert.equal(ts.maxDrawdownBps, 0);
+    assert.equal(ts.openPositions, 1, "The funded legacy ATA is an open position");
+    assert.equal(ts.positionCount, 1, "The legacy ATA is registered");
+    assert.equal(ts.syncDeadline.toNumber(), 0);
+
+    const position = await program.account.traderPosition.fetch(positionPda(legacyAltMint));
+    assert.ok(position.traderState.equals(legacyTraderState));
+    assert.ok(position.mint.equals(legacyAltMint));
+    assert.equal(position.quantity.toString(), "5000");
+    assert.equal(position.costBasis.toString(), "600000", "current_value not held in Base Asset is the position's cost");
+
+    // A second migration is rejected
+    try {
+        await program.methods.migrateTraderState()
+            .accounts(migrateAccounts)
+            .signers([legacyOwner]).rpc();
+        assert.fail("Migrated accounts should not migrate again");
+    } catch (e) {
+        assert.include(e.message, "already uses the current account layout");
+    }
+
+    // Migrated TraderStates predate journals
+    const [tradeJournalPda] = anchor.web3.PublicKey.findProgramAddressSync(
+        [Buffer.from("trade_journal"), legacyTraderState.toBuffer()],
+        program.programId
+    );
+    const journalSig = await program.methods.initTradeJournal()
+        .accounts({ payer: payer.publicKey, traderState: legacyTraderState })
+        .signers([payer]).rpc({ commitment: "confirmed" });
+
+    const journalTx = await provider.connection.getTransaction(journalSig, { commitment: "confirmed", maxSupportedTransactionVersion: 0 });
+    const journalEvent = logEvents(program, journalTx).find((e) => e.name === "tradeJournalInitialized");
+    assert.ok(journalEvent, "TradeJournalInitialized should be emitted");
+    assert.ok(journalEvent.data.tradeJournal.equals(tradeJournalPda));
+    assert.ok(journalEvent.data.traderState.equals(legacyTraderState));
+
+    // Selling the migrated position realizes PnL against its reconciled cost,
+    // so the Base Asset ATA covers current_value and settlement succeeds.
+    const mockSwap = anchor.workspace.MockSwap as Program<MockSwap>;
+    await program.methods.addSwapProgram(mockSwap.programId).accounts({
+        globalConfig: globalConfigPda, admin: payer.publicKey
+    }).signers([payer]).rpc();
+    await program.methods.resumeTraderState()
+        .accounts({ owner: legacyOwner.publicKey, traderState: legacyTraderState })
+        .signers([legacyOwner]).rpc();
+
+    const PROCEEDS = 700_000;
+    const poolAlt = await getOrCreateAssociatedTokenAccount(provider.connection, payer, legacyAltMint, payer.publicKey);
+    const poolBase = await getOrCreateAssociatedTokenAccount(provider.connection, payer, baseMint, payer.publicKey);
+    await mintTo(provider.connection, payer, baseMint, poolBase.address, payer.publicKey, PROCEEDS);
+    const config = await program.account.globalConfig.fetch(globalConfigPda);
+    const AMOUNT_IN = 5_000;
+    const fee = Math.floor(AMOUNT_IN * config.platformFeeBps / 10_000);
+    const swapIx = await mockSwap.methods.swapPair(new anchor.BN(AMOUNT_IN - fee), new anchor.BN(PROCEEDS))
+        .accounts({
+            authority: legacyTraderState,
+            input: legacyAltAta,
+            output: legacyBaseAta,
+            poolAuthority: payer.publicKey,
+            poolInput: poolAlt.address,
+            poolOutput: poolBase.address,
+            tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .instruction();
+
+    await program.methods.executeTraderSwap(new anchor.BN(AMOUNT_IN), new anchor.BN(PROCEEDS), new anchor.BN(PROCEEDS), swapIx.data)
+        .accounts({
+            authority: legacyOwner.publicKey,
+            vault: legacyVault,
+            traderState: legacyTraderState,
+            inputTokenAccount: legacyAltAta,
+            inputPosition: positionPda(legacyAltMint),
+            outputTokenAccount: legacyBaseAta,
+            platformFeeAccount: poolAlt.address,
+            globalConfig: globalConfigPda,
+            jupiterProgram: mockSwap.programId,
+            tokenProgram: TOKEN_PROGRAM_ID,
+            instructions: anchor.web3.SYSVAR_INSTRUCTIONS_PUBKEY,
+        })
+        .remainingAccounts(swapIx.keys)
+        .signers([legacyOwner, payer]).rpc();
+
+    const sold = await program.account.traderState.fetch(legacyTraderState);
+    assert.equal(sold.openPositions, 0, "The migrated position is fully sold");
+    assert.equal(sold.currentValue.toString(), "1100000", "Cost 600_000 sold for 700_000");
+    assert.equal(sold.cumulativeProfit.toString(), "-100000");
+    assert.equal((await getAccount(provider.connection, legacyBaseAta)).amount.toString(), "1100000");
+
+    await program.methods.pauseTraderState()
+        .accounts({ owner: legacyOwner.publicKey, traderState: legacyTraderState })
+        .signers([legacyOwner]).rpc();
+    await program.methods.settleTraderState().accounts({
+        owner: legacyOwner.publicKey,
+        vault: legacyVault,
+        traderState: legacyTraderState,
+        traderTokenAccount: legacyBaseAta,
+        performanceFeeAccount: poolBase.address
+    }).remainingAccounts(pair(legacyAltMint, legacyAltAta)).signers([legacyOwner]).rpc();
+
+    const settled = await program.account.traderState.fetch(legacyTraderState);
+    assert.deepEqual(settled.status, { settled: {} });
+    assert.equal(settled.highWaterMark.toString(), "1200000", "Still below the HWM, so no performance fee");
+  });
+
+  it("Books Base Asset beyond the legacy current_value as profit on migration", async () => {
+    // Preloaded by Anchor.toml for the same owner: initialized, current_value
+    // and high_water_mark 300_000. The vault was created by the test above.
+    const legacyOwner = anchor.web3.Keypair.fromSecretKey(Uint8Array.from(
+        JSON.parse(fs.readFileSync("./tests/fixtures/legacy_owner.json", "utf-8"))
+    ));
+    const legacyTraderState = new anchor.web3.PublicKey("Gq4yQSgaqSK4TVW33vUttthkUpPPYbteRv5LNv8dfMHq");
+
+    const legacyAltMint = await createMint(provider.connection, payer, payer.publicKey, null, 6);
+    const legacyAltAta = (await getOrCreateAssociatedTokenAccount(
+        provider.connection, payer, legacyAltMint, legacyTraderState, true
+    )).address;
+    await mintTo(provider.connection, payer, legacyAltMint, legacyAltAta, payer.publicKey, 1_000);
+    const legacyBaseAta = (await getOrCreateAssociatedTokenAccount(
+        provider.connection, payer, baseMint, legacyTraderState, true
+    )).address;
+    await mintTo(provider.connection, payer, baseMint, legacyBaseAta, payer.publicKey, 500_000);
+    const [altPosition] = anchor.web3.PublicKey.findProgramAddressSync(
+        [Buffer.from("trader_position"), legacyTraderState.toBuffer(), legacyAltMint.toBuffer()],
+        program.programId
+    );
+
+    await program.methods.migrateTraderState()
+        .accounts({ owner: legacyOwner.publicKey, traderState: legacyTraderState, traderTokenAccount: legacyBaseAta })
+        .remainingAccounts([
+            { pubkey: altPosition, isWritable: true, isSigner: false },
+            { pubkey: legacyAltAta, isWritable: false, isSigner: false },
+        ])
+        .signers([legacyOwner]).rpc();
+
+    const ts = await program.account.traderState.fetch(legacyTraderState);
+    assert.deepEqual(ts.status, { active: {} });
+    assert.equal(ts.currentValue.toString(), "500000", "current_value covers the Base Asset held");
+    assert.equal(ts.peakValue.toString(), "500000");
+    assert.equal(ts.cumulativeProfit.toString(), "200000", "The uncounted Base Asset is profit");
+    assert.equal(ts.highWaterMark.toString(), "300000");
+    assert.equal(ts.openPositions, 1);
+
+    const position = await program.account.traderPosition.fetch(altPosition);
+    assert.equal(position.quantity.toString(), "1000");
+    assert.equal(position.costBasis.toString(), "0", "Nothing is left unbooked to cost the position");
+  });
+
 });
@@ -4,6 +4,7 @@ import { StellalphaVault } from "../target/types/stellalpha_vault";
 import { assert } from "chai";
 import {
     createMint,
+    createAccount,
     mintTo,
     getAccount,
     TOKEN_PROGRAM_ID,
@@ -13,6 +14,7 @@ import {
 } from "@solana/spl-token";
 import * as fs from "fs";
 import * as os from "os";
+import { logEvents } from "./utils/events";
 
 describe("Phase 7: Multi-Asset Support", () => {
     const provider = anchor.AnchorProvider.env();
@@ -35,6 +37,12 @@ describe("Phase 7: Multi-Asset Support", () => {
     let traderStatePda: anchor.web3.PublicKey;
     let globalConfigPda: anchor.web3.PublicKey;
 
+    const positionPda = (traderState: anchor.web3.PublicKey, mint: anchor.web3.PublicKey) =>
+        anchor.web3.PublicKey.findProgramAddressSync(
+            [Buffer.from("trader_position"), traderState.toBuffer(), mint.toBuffer()],
+            program.programId
+        )[0];
+
     before(async () => {
         console.log("Setting up Phase 7 test environment...");
         console.log("Vault Owner (Ephemeral):", vaultOwner.publicKey.toBase58());
@@ -64,7 +72,7 @@ describe("Phase 7: Multi-Asset Support", () => {
             program.programId
         );
         try {
-            await program.methods.initializeGlobalConfig().accounts({
+            await program.methods.initializeGlobalConfig(payer.publicKey).accounts({
                 globalConfig: globalConfigPda,
                 admin: payer.publicKey,
                 systemProgram: anchor.web3.SystemProgram.programId
@@ -74,6 +82,11 @@ describe("Phase 7: Multi-Asset Support", () => {
             console.log("GlobalConfig might be already initialized.");
         }
 
+        // Swap program must be on the GlobalConfig allowlist (idempotent)
+        await program.methods.addSwapProgram(new anchor.web3.PublicKey("MemoSq4gqABAXKb96qnH8TysNcWxMyWCqXgDLGmfcQb")).accounts({
+            globalConfig: globalConfigPda, admin: payer.publicKey
+        }).signers([payer]).rpc();
+
         // UserVault
         [vaultPda] = anchor.web3.PublicKey.findProgramAddressSync(
             [Buffer.from("user_vault_v1"), vaultOwner.publicKey.toBuffer()],
@@ -124,7 +137,7 @@ describe("Phase 7: Multi-Asset Support", () => {
         );
         const traderTokenAccount = getAssociatedTokenAddressSync(baseMint, traderStatePda, true);
 
-        await program.methods.createTraderState(new anchor.BN(1_000_000)).accounts({
+        await program.methods.createTraderState(new anchor.BN(1_000_000), 0).accounts({
             owner: vaultOwner.publicKey,
             trader: trader.publicKey,
             vault: vaultPda,
@@ -144,7 +157,7 @@ describe("Phase 7: Multi-Asset Support", () => {
     // ========================================================================
     it("TraderState is NOT initialized by default", async () => {
         const ts = await program.account.traderState.fetch(traderStatePda);
-        assert.isFalse(ts.isInitialized, "TraderState should NOT be initialized by default");
+        assert.deepEqual(ts.status, { created: {} }, "TraderState should NOT be initialized by default");
         console.log("✅ TraderState.is_initialized = false by default.");
     });
 
@@ -161,6 +174,7 @@ describe("Phase 7: Multi-Asset Support", () => {
             await program.methods.executeTraderSwap(
                 new anchor.BN(100),
                 new anchor.BN(1),
+                new anchor.BN(1),
                 Buffer.from([])
             ).accounts({
                 authority: vaultOwner.publicKey,
@@ -187,18 +201,31 @@ describe("Phase 7: Multi-Asset Support", () => {
     it("create_trader_ata succeeds for owner", async () => {
         const altAta = getAssociatedTokenAddressSync(altMint, traderStatePda, true);
 
-        await program.methods.createTraderAta().accounts({
+        const sig = await program.methods.createTraderAta().accounts({
             owner: vaultOwner.publicKey,
+            vault: vaultPda,
             traderState: traderStatePda,
             mint: altMint,
             traderTokenAccount: altAta,
+            traderPosition: positionPda(traderStatePda, altMint),
             tokenProgram: TOKEN_PROGRAM_ID,
             associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
             systemProgram: anchor.web3.SystemProgram.programId
-        }).signers([vaultOwner]).rpc();
+        }).signers([vaultOwner]).rpc({ commitment: "confirmed" });
 
         const ataInfo = await getAccount(provider.connection, altAta);
         assert.equal(ataInfo.owner.toBase58(), traderStatePda.toBase58(), "ATA should be owned by TraderState PDA");
+
+        const tx = await provider.connection.getTransaction(sig, { commitment: "confirmed", maxSupportedTransactionVersion: 0 });
+        const event = logEvents(program, tx).find((e) => e.name === "traderPositionCreated");
+        assert.ok(event, "TraderPositionCreated should be emitted");
+        assert.ok(event.data.traderState.equals(traderStatePda));
+        assert.ok(event.data.mint.equals(altMint));
+
+        const position = await program.account.traderPosition.fetch(positionPda(traderStatePda, altMint));
+        assert.ok(position.traderState.equals(traderStatePda));
+        assert.ok(position.mint.equals(altMint));
+        assert.equal(position.quantity.toNumber(), 0, "Position starts empty");
         console.log("✅ create_trader_ata succeeded. ATA authority:", ataInfo.owner.toBase58());
     });
 
@@ -211,9 +238,11 @@ describe("Phase 7: Multi-Asset Support", () => {
         // Second call should succeed (init_if_needed)
         await program.methods.createTraderAta().accounts({
             owner: vaultOwner.publicKey,
+            vault: vaultPda,
             traderState: traderStatePda,
             mint: altMint,
             traderTokenAccount: altAta,
+            traderPosition: positionPda(traderStatePda, altMint),
             tokenProgram: TOKEN_PROGRAM_ID,
             associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
             systemProgram: anchor.web3.SystemProgram.programId
@@ -243,9 +272,11 @@ describe("Phase 7: Multi-Asset Support", () => {
         try {
             await program.methods.createTraderAta().accounts({
                 owner: fakeOwner.publicKey,  // Wrong owner
+                vault: vaultPda,
                 traderState: traderStatePda,
                 mint: altMint2,
                 traderTokenAccount: altMint2Ata,
+                traderPosition: positionPda(traderStatePda, altMint2),
                 tokenProgram: TOKEN_PROGRAM_ID,
                 associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
                 systemProgram: anchor.web3.SystemProgram.programId
@@ -268,8 +299,8 @@ describe("Phase 7: Multi-Asset Support", () => {
         }).signers([vaultOwner]).rpc();
 
         const ts = await program.account.traderState.fetch(traderStatePda);
-        assert.isTrue(ts.isInitialized, "TraderState should be initialized");
-        console.log("✅ mark_trader_initialized succeeded. is_initialized:", ts.isInitialized);
+        assert.deepEqual(ts.status, { active: {} }, "TraderState should be initialized");
+        console.log("✅ mark_trader_initialized succeeded. status:", Object.keys(ts.status)[0]);
     });
 
     // ========================================================================
@@ -298,18 +329,22 @@ describe("Phase 7: Multi-Asset Support", () => {
             provider.connection, payer, baseMint, payer.publicKey
         );
 
-        // Since we're using Memo mock, this should succeed if initialization is correct
+        const altAta = getAssociatedTokenAddressSync(altMint, traderStatePda, true);
+
+        // Base -> Alt through the Memo venue should succeed if initialization is correct
         try {
             await program.methods.executeTraderSwap(
                 new anchor.BN(100),
                 new anchor.BN(1),
+                new anchor.BN(1),
                 Buffer.from([])
             ).accounts({
                 authority: vaultOwner.publicKey,
                 vault: vaultPda,
                 traderState: traderStatePda,
                 inputTokenAccount: traderBaseAta,
-                outputTokenAccount: traderBaseAta,
+                outputTokenAccount: altAta,
+                outputPosition: positionPda(traderStatePda, altMint),
                 platformFeeAccount: platformFeeAta.address,
                 globalConfig: globalConfigPda,
                 jupiterProgram: new anchor.web3.PublicKey("MemoSq4gqABAXKb96qnH8TysNcWxMyWCqXgDLGmfcQb"),
@@ -318,11 +353,11 @@ describe("Phase 7: Multi-Asset Support", () => {
             }).signers([vaultOwner]).rpc();
             console.log("✅ execute_trader_swap works after initialization.");
         } catch (e: any) {
-            // May fail due to Memo mock limitations, but NOT due to initialization
+            // May fail for other reasons (e.g. the whitelist), but NOT due to initialization
             if (e.message.includes("not initialized")) {
                 assert.fail("Should NOT fail with TraderNotInitialized after marking initialized");
             }
-            console.log("✅ execute_trader_swap passed initialization check (other error OK for mock).");
+            console.log("✅ execute_trader_swap passed initialization check (other error OK here).");
         }
     });
 
@@ -339,6 +374,7 @@ describe("Phase 7: Multi-Asset Support", () => {
                 owner: vaultOwner.publicKey,
                 traderState: traderStatePda,
                 traderTokenAccount: altAta,
+                traderPosition: positionPda(traderStatePda, altMint),
                 tokenProgram: TOKEN_PROGRAM_ID
             }).signers([vaultOwner]).rpc();
             assert.fail("Should have failed with TraderNotPaused");
@@ -356,28 +392,42 @@ describe("Phase 7: Multi-Asset Support", () => {
         }).signers([vaultOwner]).rpc();
 
         const ts = await program.account.traderState.fetch(traderStatePda);
-        assert.isTrue(ts.isPaused, "TraderState should be paused");
+        assert.deepEqual(ts.status, { paused: {} }, "TraderState should be paused");
         console.log("✅ TraderState paused for cleanup tests.");
     });
 
-    // Test 11: close_trader_ata fails if balance > 0
-    it("close_trader_ata fails if balance > 0", async () => {
-        // First, fund the altMint ATA
+    // Test 11: close_trader_ata sweeps an untracked balance to the owner
+    it("close_trader_ata sweeps a non-zero balance to the owner", async () => {
+        // Tokens sent straight to the ATA are not part of the position
         const altAta = getAssociatedTokenAddressSync(altMint, traderStatePda, true);
         await mintTo(provider.connection, payer, altMint, altAta, payer.publicKey, 1000);
+        const closeAccounts = {
+            owner: vaultOwner.publicKey,
+            traderState: traderStatePda,
+            traderTokenAccount: altAta,
+            traderPosition: positionPda(traderStatePda, altMint),
+            tokenProgram: TOKEN_PROGRAM_ID
+        };
 
         try {
-            await program.methods.closeTraderAta().accounts({
-                owner: vaultOwner.publicKey,
-                traderState: traderStatePda,
-                traderTokenAccount: altAta,
-                tokenProgram: TOKEN_PROGRAM_ID
-            }).signers([vaultOwner]).rpc();
+            await program.methods.closeTraderAta().accounts(closeAccounts).signers([vaultOwner]).rpc();
             assert.fail("Should have failed with NonZeroBalance");
         } catch (e: any) {
             assert.include(e.message, "non-zero balance");
-            console.log("✅ close_trader_ata correctly rejects non-zero balance.");
         }
+
+        const ownerAltAta = await getOrCreateAssociatedTokenAccount(
+            provider.connection, payer, altMint, vaultOwner.publicKey
+        );
+        await program.methods.closeTraderAta().accounts({
+            ...closeAccounts,
+            ownerTokenAccount: ownerAltAta.address
+        }).signers([vaultOwner]).rpc();
+
+        const swept = await getAccount(provider.connection, ownerAltAta.address);
+        assert.equal(Number(swept.amount), 1000, "Balance swept to the owner");
+        assert.isNull(await provider.connection.getAccountInfo(altAta), "ATA should be closed");
+        console.log("✅ close_trader_ata swept the balance to the owner and closed the ATA.");
     });
 
     // Test 12: close_trader_ata fails for non-owner
@@ -397,9 +447,11 @@ describe("Phase 7: Multi-Asset Support", () => {
         const altMint2Ata = getAssociatedTokenAddressSync(altMint2, traderStatePda, true);
         await program.methods.createTraderAta().accounts({
             owner: vaultOwner.publicKey,
+            vault: vaultPda,
             traderState: traderStatePda,
             mint: altMint2,
             traderTokenAccount: altMint2Ata,
+            traderPosition: positionPda(traderStatePda, altMint2),
             tokenProgram: TOKEN_PROGRAM_ID,
             associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
             systemProgram: anchor.web3.SystemProgram.programId
@@ -410,6 +462,7 @@ describe("Phase 7: Multi-Asset Support", () => {
                 owner: fakeOwner.publicKey,  // Wrong owner
                 traderState: traderStatePda,
                 traderTokenAccount: altMint2Ata,
+                traderPosition: positionPda(traderStatePda, altMint2),
                 tokenProgram: TOKEN_PROGRAM_ID
             }).signers([fakeOwner]).rpc();
             assert.fail("Should have failed with Unauthorized");
@@ -422,13 +475,23 @@ describe("Phase 7: Multi-Asset Support", () => {
     // Test 13: close_trader_ata succeeds when empty and paused
     it("close_trader_ata succeeds when empty and paused", async () => {
         const altMint2Ata = getAssociatedTokenAddressSync(altMint2, traderStatePda, true);
+        const before = await program.account.traderState.fetch(traderStatePda);
 
-        await program.methods.closeTraderAta().accounts({
+        const sig = await program.methods.closeTraderAta().accounts({
             owner: vaultOwner.publicKey,
             traderState: traderStatePda,
             traderTokenAccount: altMint2Ata,
+            traderPosition: positionPda(traderStatePda, altMint2),
             tokenProgram: TOKEN_PROGRAM_ID
-        }).signers([vaultOwner]).rpc();
+        }).signers([vaultOwner]).rpc({ commitment: "confirmed" });
+
+        const tx = await provider.connection.getTransaction(sig, { commitment: "confirmed", maxSupportedTransactionVersion: 0 });
+        const event = logEvents(program, tx).find((e) => e.name === "traderPositionClosed");
+        assert.ok(event, "TraderPositionClosed should be emitted");
+        assert.ok(event.data.traderState.equals(traderStatePda));
+        assert.ok(event.data.mint.equals(altMint2));
+        assert.equal(event.data.positionCount, before.positionCount - 1);
+        assert.equal(event.data.swept.toNumber(), 0);
 
         // Verify ATA is closed
         try {
@@ -455,7 +518,7 @@ describe("Phase 7: Multi-Asset Support", () => {
         const vaultTokenAccount = getAssociatedTokenAddressSync(baseMint, vaultPda, true);
 
         // Create TraderState
-        await program.methods.createTraderState(new anchor.BN(100_000)).accounts({
+        await program.methods.createTraderState(new anchor.BN(100_000), 0).accounts({
             owner: vaultOwner.publicKey,
             trader: newTrader.publicKey,
             vault: vaultPda,
@@ -476,7 +539,7 @@ describe("Phase 7: Multi-Asset Support", () => {
         }).signers([vaultOwner]).rpc();
 
         const ts = await program.account.traderState.fetch(newTraderStatePda);
-        assert.isTrue(ts.isInitialized, "Backend authority should be able to mark initialized");
+        assert.deepEqual(ts.status, { active: {} }, "Backend authority should be able to mark initialized");
         console.log("✅ Backend authority can mark_trader_initialized.");
     });
 
@@ -492,7 +555,7 @@ describe("Phase 7: Multi-Asset Support", () => {
         const vaultTokenAccount = getAssociatedTokenAddressSync(baseMint, vaultPda, true);
 
         // Create TraderState
-        await program.methods.createTraderState(new anchor.BN(100_000)).accounts({
+        await program.methods.createTraderState(new anchor.BN(100_000), 0).accounts({
             owner: vaultOwner.publicKey,
             trader: newTrader2.publicKey,
             vault: vaultPda,
@@ -550,6 +613,7 @@ describe("Phase 7: Multi-Asset Support", () => {
             await program.methods.executeTraderSwap(
                 new anchor.BN(100),
                 new anchor.BN(0),
+                new anchor.BN(0),
                 Buffer.from([])
             ).accounts({
                 authority: vaultOwner.publicKey,
@@ -586,9 +650,11 @@ describe("Phase 7: Multi-Asset Support", () => {
         const altMintAta = getAssociatedTokenAddressSync(altMint, traderStatePda, true);
         await program.methods.createTraderAta().accounts({
             owner: vaultOwner.publicKey,
+            vault: vaultPda,
             traderState: traderStatePda,
             mint: altMint,
             traderTokenAccount: altMintAta,
+            traderPosition: positionPda(traderStatePda, altMint),
             tokenProgram: TOKEN_PROGRAM_ID,
             associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
             systemProgram: anchor.web3.SystemProgram.programId
@@ -602,7 +668,8 @@ describe("Phase 7: Multi-Asset Support", () => {
                 owner: vaultOwner.publicKey,
                 vault: vaultPda,
                 traderState: traderStatePda,
-                traderTokenAccount: altMintAta  // Non-base mint!
+                traderTokenAccount: altMintAta,  // Non-base mint!
+                performanceFeeAccount: altMintAta
             }).signers([vaultOwner]).rpc();
             assert.fail("Should have failed with MintMismatch");
         } catch (e: any) {
@@ -615,4 +682,517 @@ describe("Phase 7: Multi-Asset Support", () => {
         }
     });
 
+    // Test 18: Settle must prove every non-base ATA is empty
+    it("fails_settle_with_stranded_non_base_tokens", async () => {
+        const traderBaseAta = getAssociatedTokenAddressSync(baseMint, traderStatePda, true);
+        const altMintAta = getAssociatedTokenAddressSync(altMint, traderStatePda, true);
+        const platformFeeAta = await getOrCreateAssociatedTokenAccount(
+            provider.connection, payer, baseMint, payer.publicKey
+        );
+        const ts = await program.account.traderState.fetch(traderStatePda);
+        assert.equal(ts.positionCount, 1, "altMint position remains registered");
+
+        const settle = () => program.methods.settleTraderState().accounts({
+            owner: vaultOwner.publicKey,
+            vault: vaultPda,
+            traderState: traderStatePda,
+            traderTokenAccount: traderBaseAta,
+            performanceFeeAccount: platformFeeAta.address
+        });
+
+        try {
+            await settle().signers([vaultOwner]).rpc();
+            assert.fail("Should have failed with SettlementAccountsMismatch");
+        } catch (e: any) {
+            assert.include(e.message, "must include every TraderPosition");
+        }
+
+        try {
+            await settle().remainingAccounts([
+                { pubkey: positionPda(traderStatePda, altMint), isWritable: false, isSigner: false },
+                { pubkey: altMintAta, isWritable: false, isSigner: false },
+            ]).signers([vaultOwner]).rpc();
+            assert.fail("Should have failed with StrandedAssets");
+        } catch (e: any) {
+            assert.include(e.message, "still holds tokens");
+        }
+    });
+
+    // Test 19: Explicit sync lifecycle
+    it("start_trader_sync / finish_trader_sync gate the sync phase", async () => {
+        const syncTrader = anchor.web3.Keypair.generate();
+        const [syncTraderStatePda] = anchor.web3.PublicKey.findProgramAddressSync(
+            [Buffer.from("trader_state"), vaultOwner.publicKey.toBuffer(), syncTrader.publicKey.toBuffer()],
+            program.programId
+        );
+        await program.methods.createTraderState(new anchor.BN(100_000), 0).accounts({
+            owner: vaultOwner.publicKey,
+            trader: syncTrader.publicKey,
+            vault: vaultPda,
+            traderState: syncTraderStatePda,
+            vaultTokenAccount: getAssociatedTokenAddressSync(baseMint, vaultPda, true),
+            traderTokenAccount: getAssociatedTokenAddressSync(baseMint, syncTraderStatePda, true),
+            mint: baseMint,
+            systemProgram: anchor.web3.SystemProgram.programId,
+            tokenProgram: TOKEN_PROGRAM_ID,
+            associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID
+        }).signers([vaultOwner]).rpc();
+
+        const syncAccounts = { authority: vaultOwner.publicKey, vault: vaultPda, traderState: syncTraderStatePda };
+
+        // Random signer is not the backend authority
+        const stranger = anchor.web3.Keypair.generate();
+        try {
+            await program.methods.startTraderSync()
+                .accounts({ ...syncAccounts, authority: stranger.publicKey })
+                .signers([stranger]).rpc();
+            assert.fail("Should have failed with Unauthorized");
+        } catch (e: any) {
+            assert.ok(e.message.includes("Unauthorized") || e.message.includes("not authorized") || e.message.includes("Signature"));
+        }
+
+        await program.methods.startTraderSync().accounts(syncAccounts).signers([vaultOwner]).rpc();
+        let ts = await program.account.traderState.fetch(syncTraderStatePda);
+        assert.deepEqual(ts.status, { syncing: {} });
+        assert.isAbove(ts.syncDeadline.toNumber(), 0);
+
+        // Only the backend ends a sync
+        try {
+            await program.methods.markTraderInitialized().accounts({
+                signer: vaultOwner.publicKey,
+                vault: vaultPda,
+                traderState: syncTraderStatePda
+            }).signers([vaultOwner]).rpc();
+            assert.fail("Should have failed with AlreadySyncing");
+        } catch (e: any) {
+            assert.include(e.message, "already in sync phase");
+        }
+
+        // Sync swaps are flagged in the journal (Base -> Alt, the Memo venue fills nothing)
+        const syncBaseAta = getAssociatedTokenAddressSync(baseMint, syncTraderStatePda, true);
+        const syncAltAta = getAssociatedTokenAddressSync(altMint, syncTraderStatePda, true);
+        const platformFeeAta = await getOrCreateAssociatedTokenAccount(
+            provider.connection, payer, baseMint, payer.publicKey
+        );
+        await program.methods.createTraderAta().accounts({
+            owner: vaultOwner.publicKey,
+            vault: vaultPda,
+            traderState: syncTraderStatePda,
+            mint: altMint,
+            traderTokenAccount: syncAltAta,
+            traderPosition: positionPda(syncTraderStatePda, altMint),
+            tokenProgram: TOKEN_PROGRAM_ID,
+            associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+            systemProgram: anchor.web3.SystemProgram.programId
+        }).signers([vaultOwner]).rpc();
+        await program.methods.addAllowedMint(altMint)
+            .accounts({ owner: vaultOwner.publicKey, vault: vaultPda })
+            .signers([vaultOwner]).rpc();
+        await program.methods.executeTraderSwap(new anchor.BN(1_000), new anchor.BN(0), new anchor.BN(0), Buffer.from("MEMO"))
+            .accounts({
+                authority: vaultOwner.publicKey,
+                vault: vaultPda,
+                traderState: syncTraderStatePda,
+                inputTokenAccount: syncBaseAta,
+                outputTokenAccount: syncAltAta,
+                outputPosition: positionPda(syncTraderStatePda, altMint),
+                platformFeeAccount: platformFeeAta.address,
+                globalConfig: globalConfigPda,
+                jupiterProgram: new anchor.web3.PublicKey("MemoSq4gqABAXKb96qnH8TysNcWxMyWCqXgDLGmfcQb"),
+                tokenProgram: TOKEN_PROGRAM_ID,
+                instructions: anchor.web3.SYSVAR_INSTRUCTIONS_PUBKEY
+            }).signers([vaultOwner]).rpc();
+        // Leave the vault on the (empty) default whitelist for later tests
+        await program.methods.removeAllowedMint(altMint)
+            .accounts({ owner: vaultOwner.publicKey, vault: vaultPda })
+            .signers([vaultOwner]).rpc();
+        const [syncJournalPda] = anchor.web3.PublicKey.findProgramAddressSync(
+            [Buffer.from("trade_journal"), syncTraderStatePda.toBuffer()],
+            program.programId
+        );
+        const syncJournal = await program.account.tradeJournal.fetch(syncJournalPda);
+        assert.equal(syncJournal.entries[0].isSync, 1);
+
+        // Owner cannot abort before the deadline
+        try {
+            await program.methods.abortTraderSync()
+                .accounts({ owner: vaultOwner.publicKey, traderState: syncTraderStatePda })
+                .signers([vaultOwner]).rpc();
+            assert.fail("Should have failed with SyncDeadlineNotReached");
+        } catch (e: any) {
+            assert.include(e.message, "deadline has not passed");
+        }
+
+        // A paused vault cannot start automated trading
+        await program.methods.pauseVault()
+            .accounts({ owner: vaultOwner.publicKey, vault: vaultPda })
+            .signers([vaultOwner]).rpc();
+        try {
+            await program.methods.finishTraderSync().accounts(syncAccounts).signers([vaultOwner]).rpc();
+            assert.fail("Should have failed with Paused");
+        } catch (e: any) {
+            assert.include(e.message, "The vault is currently paused");
+        }
+        await program.methods.unpauseVault()
+            .accounts({ owner: vaultOwner.publicKey, vault: vaultPda })
+            .signers([vaultOwner]).rpc();
+
+        ts = await program.account.traderState.fetch(syncTraderStatePda);
+        assert.isAbove(ts.highWaterMark.toNumber(), ts.currentValue.toNumber(), "Sync fees leave value below the HWM");
+        const syncedValue = ts.currentValue.toString();
+
+        await program.methods.finishTraderSync().accounts(syncAccounts).signers([vaultOwner]).rpc();
+        ts = await program.account.traderState.fetch(syncTraderStatePda);
+        assert.deepEqual(ts.status, { active: {} });
+        // The synced portfolio is the drawdown and fee baseline
+        assert.equal(ts.peakValue.toString(), syncedValue);
+        assert.equal(ts.highWaterMark.toString(), syncedValue);
+
+        try {
+            await program.methods.startTraderSync().accounts(syncAccounts).signers([vaultOwner]).rpc();
+            assert.fail("Should have failed with AlreadyInitialized");
+        } catch (e: any) {
+            assert.include(e.message, "already initialized");
+        }
+    });
+
+    // Test 20: Settling -> Settled, with illegal transitions rejected
+    it("begin_trader_settlement moves through Settling to Settled", async () => {
+        const exitTrader = anchor.web3.Keypair.generate();
+        const [exitTraderStatePda] = anchor.web3.PublicKey.findProgramAddressSync(
+            [Buffer.from("trader_state"), vaultOwner.publicKey.toBuffer(), exitTrader.publicKey.toBuffer()],
+            program.programId
+        );
+        const exitBaseAta = getAssociatedTokenAddressSync(baseMint, exitTraderStatePda, true);
+        await program.methods.createTraderState(new anchor.BN(100_000), 0).accounts({
+            owner: vaultOwner.publicKey,
+            trader: exitTrader.publicKey,
+            vault: vaultPda,
+            traderState: exitTraderStatePda,
+            vaultTokenAccount: getAssociatedTokenAddressSync(baseMint, vaultPda, true),
+            traderTokenAccount: exitBaseAta,
+            mint: baseMint,
+            systemProgram: anchor.web3.SystemProgram.programId,
+            tokenProgram: TOKEN_PROGRAM_ID,
+            associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID
+        }).signers([vaultOwner]).rpc();
+        await program.methods.markTraderInitialized().accounts({
+            signer: vaultOwner.publicKey,
+            vault: vaultPda,
+            traderState: exitTraderStatePda
+        }).signers([vaultOwner]).rpc();
+
+        const ownerAccounts = { owner: vaultOwner.publicKey, traderState: exitTraderStatePda };
+        await program.methods.beginTraderSettlement().accounts(ownerAccounts).signers([vaultOwner]).rpc();
+        let ts = await program.account.traderState.fetch(exitTraderStatePda);
+        assert.deepEqual(ts.status, { settling: {} });
+
+        try {
+            await program.methods.resumeTraderState().accounts(ownerAccounts).signers([vaultOwner]).rpc();
+            assert.fail("Should have failed with TraderSettling");
+        } catch (e: any) {
+            assert.include(e.message, "TraderState is settling");
+        }
+
+        try {
+            await program.methods.startTraderSync()
+                .accounts({ authority: vaultOwner.publicKey, vault: vaultPda, traderState: exitTraderStatePda })
+                .signers([vaultOwner]).rpc();
+            assert.fail("Should have failed with TraderSettling");
+        } catch (e: any) {
+            assert.include(e.message, "TraderState is settling");
+        }
+
+        const platformFeeAta = await getOrCreateAssociatedTokenAccount(
+            provider.connection, payer, baseMint, payer.publicKey
+        );
+        await program.methods.settleTraderState().accounts({
+            owner: vaultOwner.publicKey,
+            vault: vaultPda,
+            traderState: exitTraderStatePda,
+            traderTokenAccount: exitBaseAta,
+            performanceFeeAccount: platformFeeAta.address
+        }).signers([vaultOwner]).rpc();
+        ts = await program.account.traderState.fetch(exitTraderStatePda);
+        assert.deepEqual(ts.status, { settled: {} });
+
+        try {
+            await program.methods.resumeTraderState().accounts(ownerAccounts).signers([vaultOwner]).rpc();
+            assert.fail("Should have failed with AlreadySettled");
+        } catch (e: any) {
+            assert.include(e.message, "already settled");
+        }
+    });
+
+    // Test 21: The Base Asset never gets a TraderPosition
+    it("create_trader_ata rejects the Base Asset", async () => {
+        try {
+            await program.methods.createTraderAta().accounts({
+                owner: vaultOwner.publicKey,
+                vault: vaultPda,
+                traderState: traderStatePda,
+                mint: baseMint,
+                traderTokenAccount: getAssociatedTokenAddressSync(baseMint, traderStatePda, true),
+                traderPosition: positionPda(traderStatePda, baseMint),
+                tokenProgram: TOKEN_PROGRAM_ID,
+                associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+                systemProgram: anchor.web3.SystemProgram.programId
+            }).signers([vaultOwner]).rpc();
+            assert.fail("Should have failed with BaseMintPosition");
+        } catch (e: any) {
+            assert.include(e.message, "cannot have a TraderPosition");
+        }
+    });
+
+    // Settled TraderState that still has an (empty) altMint ATA and TraderPosition
+    const createSettledTraderStateWithPosition = async () => {
+        const exitTrader = anchor.web3.Keypair.generate();
+        const [exitTraderStatePda] = anchor.web3.PublicKey.findProgramAddressSync(
+            [Buffer.from("trader_state"), vaultOwner.publicKey.toBuffer(), exitTrader.publicKey.toBuffer()],
+            program.programId
+        );
+        const exitBaseAta = getAssociatedTokenAddressSync(baseMint, exitTraderStatePda, true);
+        const exitAltAta = getAssociatedTokenAddressSync(altMint, exitTraderStatePda, true);
+        const vaultTokenAccount = getAssociatedTokenAddressSync(baseMint, vaultPda, true);
+        await program.methods.createTraderState(new anchor.BN(100_000), 0).accounts({
+            owner: vaultOwner.publicKey,
+            trader: exitTrader.publicKey,
+            vault: vaultPda,
+            traderState: exitTraderStatePda,
+            vaultTokenAccount: vaultTokenAccount,
+            traderTokenAccount: exitBaseAta,
+            mint: baseMint,
+            systemProgram: anchor.web3.SystemProgram.programId,
+            tokenProgram: TOKEN_PROGRAM_ID,
+            associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID
+        }).signers([vaultOwner]).rpc();
+        await program.methods.createTraderAta().accounts({
+            owner: vaultOwner.publicKey,
+            vault: vaultPda,
+            traderState: exitTraderStatePda,
+            mint: altMint,
+            traderTokenAccount: exitAltAta,
+            traderPosition: positionPda(exitTraderStatePda, altMint),
+            tokenProgram: TOKEN_PROGRAM_ID,
+            associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+            systemProgram: anchor.web3.SystemProgram.programId
+        }).signers([vaultOwner]).rpc();
+
+        await program.methods.pauseTraderState()
+            .accounts({ owner: vaultOwner.publicKey, traderState: exitTraderStatePda })
+            .signers([vaultOwner]).rpc();
+        const platformFeeAta = await getOrCreateAssociatedTokenAccount(
+            provider.connection, payer, baseMint, payer.publicKey
+        );
+        await program.methods.settleTraderState().accounts({
+            owner: vaultOwner.publicKey,
+            vault: vaultPda,
+            traderState: exitTraderStatePda,
+            traderTokenAccount: exitBaseAta,
+            performanceFeeAccount: platformFeeAta.address
+        }).remainingAccounts([
+            { pubkey: positionPda(exitTraderStatePda, altMint), isWritable: false, isSigner: false },
+            { pubkey: exitAltAta, isWritable: false, isSigner: false },
+        ]).signers([vaultOwner]).rpc();
+        return { exitTraderStatePda, exitBaseAta, exitAltAta, vaultTokenAccount };
+    };
+
+    // Test 22: Withdrawal requires every non-base ATA to be closed first
+    it("withdraw_trader_state fails while a non-base ATA remains", async () => {
+        const { exitTraderStatePda, exitBaseAta, exitAltAta, vaultTokenAccount } =
+            await createSettledTraderStateWithPosition();
+
+        const ownerBaseAta = await getOrCreateAssociatedTokenAccount(
+            provider.connection, payer, baseMint, vaultOwner.publicKey
+        );
+        const withdraw = () => program.methods.withdrawTraderState().accounts({
+            owner: vaultOwner.publicKey,
+            vault: vaultPda,
+            traderState: exitTraderStatePda,
+            traderTokenAccount: exitBaseAta,
+            vaultTokenAccount: vaultTokenAccount,
+            ownerTokenAccount: ownerBaseAta.address,
+            tokenProgram: TOKEN_PROGRAM_ID
+        }).signers([vaultOwner]);
+
+        try {
+            await withdraw().rpc();
+            assert.fail("Should have failed with PositionAccountsRemain");
+        } catch (e: any) {
+            assert.include(e.message, "Close every non-base TraderState ATA");
+        }
+
+        await program.methods.closeTraderAta().accounts({
+            owner: vaultOwner.publicKey,
+            traderState: exitTraderStatePda,
+            traderTokenAccount: exitAltAta,
+            traderPosition: positionPda(exitTraderStatePda, altMint),
+            tokenProgram: TOKEN_PROGRAM_ID
+        }).signers([vaultOwner]).rpc();
+        await withdraw().rpc();
+
+        const info = await provider.connection.getAccountInfo(exitTraderStatePda);
+        assert.isNull(info, "TraderState should be closed");
+    });
+
+    // Test 23: Closing requires every non-base ATA to be closed first
+    it("close_trader_state fails while a non-base ATA remains", async () => {
+        const { exitTraderStatePda, exitBaseAta, exitAltAta, vaultTokenAccount } =
+            await createSettledTraderStateWithPosition();
+        const close = () => program.methods.closeTraderState().accounts({
+            owner: vaultOwner.publicKey,
+            traderState: exitTraderStatePda,
+            vault: vaultPda,
+            traderTokenAccount: exitBaseAta,
+            vaultTokenAccount: vaultTokenAccount,
+            tokenProgram: TOKEN_PROGRAM_ID
+        }).signers([vaultOwner]);
+
+        try {
+            await close().rpc();
+            assert.fail("Should have failed with PositionAccountsRemain");
+        } catch (e: any) {
+            assert.include(e.message, "Close every non-base TraderState ATA");
+        }
+
+        await program.methods.closeTraderAta().accounts({
+            owner: vaultOwner.publicKey,
+            traderState: exitTraderStatePda,
+            traderTokenAccount: exitAltAta,
+            traderPosition: positionPda(exitTraderStatePda, altMint),
+            tokenProgram: TOKEN_PROGRAM_ID
+        }).signers([vaultOwner]).rpc();
+        await close().rpc();
+
+        const info = await provider.connection.getAccountInfo(exitTraderStatePda);
+        assert.isNull(info, "TraderState should be closed");
+    });
+
+    // Test 24: close_trader_ata only accepts the real ATA
+    it("close_trader_ata rejects a non-ATA token account", async () => {
+        const { exitTraderStatePda, exitAltAta } = await createSettledTraderStateWithPosition();
+
+        // Tokens land in the ATA; an empty look-alike account must not stand in for it
+        await mintTo(provider.connection, payer, altMint, exitAltAta, payer.publicKey, 1000);
+        const decoy = await createAccount(
+            provider.connection, payer, altMint, exitTraderStatePda, anchor.web3.Keypair.generate()
+        );
+
+        try {
+            await program.methods.closeTraderAta().accounts({
+                owner: vaultOwner.publicKey,
+                traderState: exitTraderStatePda,
+                traderTokenAccount: decoy,
+                traderPosition: positionPda(exitTraderStatePda, altMint),
+                tokenProgram: TOKEN_PROGRAM_ID
+            }).signers([vaultOwner]).rpc();
+            assert.fail("Should have failed with ConstraintAssociated");
+        } catch (e: any) {
+            assert.include(e.message, "ConstraintAssociated");
+        }
+
+        const ts = await program.account.traderState.fetch(exitTraderStatePda);
+        assert.equal(ts.positionCount, 1, "Position stays registered");
+    });
+
+    // Test 25: Dust sent to a position ATA cannot lock the owner out
+    it("owner can settle and withdraw after dust is sent to a position ATA", async () => {
+        const exitTrader = anchor.web3.Keypair.generate();
+        const [exitTraderStatePda] = anchor.web3.PublicKey.findProgramAddressSync(
+            [Buffer.from("trader_state"), vaultOwner.publicKey.toBuffer(), exitTrader.publicKey.toBuffer()],
+            program.programId
+        );
+        const exitBaseAta = getAssociatedTokenAddressSync(baseMint, exitTraderStatePda, true);
+        const exitAltAta = getAssociatedTokenAddressSync(altMint, exitTraderStatePda, true);
+        const vaultTokenAccount = getAssociatedTokenAddressSync(baseMint, vaultPda, true);
+        await program.methods.createTraderState(new anchor.BN(100_000), 0).accounts({
+            owner: vaultOwner.publicKey,
+            trader: exitTrader.publicKey,
+            vault: vaultPda,
+            traderState: exitTraderStatePda,
+            vaultTokenAccount: vaultTokenAccount,
+            traderTokenAccount: exitBaseAta,
+            mint: baseMint,
+            systemProgram: anchor.web3.SystemProgram.programId,
+            tokenProgram: TOKEN_PROGRAM_ID,
+            associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID
+        }).signers([vaultOwner]).rpc();
+        await program.methods.createTraderAta().accounts({
+            owner: vaultOwner.publicKey,
+            vault: vaultPda,
+            traderState: exitTraderStatePda,
+            mint: altMint,
+            traderTokenAccount: exitAltAta,
+            traderPosition: positionPda(exitTraderStatePda, altMint),
+            tokenProgram: TOKEN_PROGRAM_ID,
+            associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+            systemProgram: anchor.web3.SystemProgram.programId
+        }).signers([vaultOwner]).rpc();
+        await program.methods.pauseTraderState()
+            .accounts({ owner: vaultOwner.publicKey, traderState: exitTraderStatePda })
+            .signers([vaultOwner]).rpc();
+
+        // Anyone can send 1 unit to the ATA
+        await mintTo(provider.connection, payer, altMint, exitAltAta, payer.publicKey, 1);
+
+        const platformFeeAta = await getOrCreateAssociatedTokenAccount(
+            provider.connection, payer, baseMint, payer.publicKey
+        );
+        try {
+            await program.methods.settleTraderState().accounts({
+                owner: vaultOwner.publicKey,
+                vault: vaultPda,
+                traderState: exitTraderStatePda,
+                traderTokenAccount: exitBaseAta,
+                performanceFeeAccount: platformFeeAta.address
+            }).remainingAccounts([
+                { pubkey: positionPda(exitTraderStatePda, altMint), isWritable: false, isSigner: false },
+                { pubkey: exitAltAta, isWritable: false, isSigner: false },
+            ]).signers([vaultOwner]).rpc();
+            assert.fail("Should have failed with StrandedAssets");
+        } catch (e: any) {
+            assert.include(e.message, "still holds tokens");
+        }
+
+        const ownerAltAta = await getOrCreateAssociatedTokenAccount(
+            provider.connection, payer, altMint, vaultOwner.publicKey
+        );
+        const altBefore = await getAccount(provider.connection, ownerAltAta.address);
+        await program.methods.closeTraderAta().accounts({
+            owner: vaultOwner.publicKey,
+            traderState: exitTraderStatePda,
+            traderTokenAccount: exitAltAta,
+            traderPosition: positionPda(exitTraderStatePda, altMint),
+            ownerTokenAccount: ownerAltAta.address,
+            tokenProgram: TOKEN_PROGRAM_ID
+        }).signers([vaultOwner]).rpc();
+        const altAfter = await getAccount(provider.connection, ownerAltAta.address);
+        assert.equal(Number(altAfter.amount - altBefore.amount), 1, "Dust swept to the owner");
+
+        await program.methods.settleTraderState().accounts({
+            owner: vaultOwner.publicKey,
+            vault: vaultPda,
+            traderState: exitTraderStatePda,
+            traderTokenAccount: exitBaseAta,
+            performanceFeeAccount: platformFeeAta.address
+        }).signers([vaultOwner]).rpc();
+
+        const ownerBaseAta = await getOrCreateAssociatedTokenAccount(
+            provider.connection, payer, baseMint, vaultOwner.publicKey
+        );
+        const baseBefore = await getAccount(provider.connection, ownerBaseAta.address);
+        await program.methods.withdrawTraderState().accounts({
+            owner: vaultOwner.publicKey,
+            vault: vaultPda,
+            traderState: exitTraderStatePda,
+            traderTokenAccount: exitBaseAta,
+            vaultTokenAccount: vaultTokenAccount,
+            ownerTokenAccount: ownerBaseAta.address,
+            tokenProgram: TOKEN_PROGRAM_ID
+        }).signers([vaultOwner]).rpc();
+
+        const baseAfter = await getAccount(provider.connection, ownerBaseAta.address);
+        assert.equal(Number(baseAfter.amount - baseBefore.amount), 100_000, "Owner withdrew the full allocation");
+        assert.isNull(await provider.connection.getAccountInfo(exitTraderStatePda), "TraderState should be closed");
+    });
 });
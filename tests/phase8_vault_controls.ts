@@ -0,0 +1,546 @@
+import * as anchor from "@coral-xyz/anchor";
+import { Program } from "@coral-xyz/anchor";
+import { StellalphaVault } from "../target/types/stellalpha_vault";
+import { MockSwap } from "../target/types/mock_swap";
+import { assert } from "chai";
+import {
+  createMint,
+  mintTo,
+  getOrCreateAssociatedTokenAccount,
+  getAssociatedTokenAddressSync,
+  getAccount,
+  TOKEN_PROGRAM_ID,
+  ASSOCIATED_TOKEN_PROGRAM_ID
+} from "@solana/spl-token";
+import * as fs from "fs";
+import * as os from "os";
+
+describe("Phase 8: Vault Controls", () => {
+  const provider = anchor.AnchorProvider.env();
+  anchor.setProvider(provider);
+
+  const program = anchor.workspace.StellalphaVault as Program<StellalphaVault>;
+  const mockSwap = anchor.workspace.MockSwap as Program<MockSwap>;
+
+  const walletPath = os.homedir() + "/.config/solana/devnet-wallet.json";
+  const rawKey = JSON.parse(fs.readFileSync(walletPath, "utf-8"));
+  const payer = anchor.web3.Keypair.fromSecretKey(Uint8Array.from(rawKey));
+
+  const vaultOwner = anchor.web3.Keypair.generate();
+  const backendAuthority = anchor.web3.Keypair.generate();
+
+  let baseMint: anchor.web3.PublicKey;
+  let vaultPda: anchor.web3.PublicKey;
+  let vaultTokenAccount: anchor.web3.PublicKey;
+
+  const FUNDING_AMOUNT = new anchor.BN(1_000_000);
+
+  before(async () => {
+    const transferTx = new anchor.web3.Transaction().add(
+        anchor.web3.SystemProgram.transfer({
+            fromPubkey: payer.publicKey,
+            toPubkey: vaultOwner.publicKey,
+            lamports: 100_000_000
+        })
+    );
+    await anchor.web3.sendAndConfirmTransaction(provider.connection, transferTx, [payer]);
+
+    baseMint = await createMint(provider.connection, payer, payer.publicKey, null, 6);
+
+    [vaultPda] = anchor.web3.PublicKey.findProgramAddressSync(
+        [Buffer.from("user_vault_v1"), vaultOwner.publicKey.toBuffer()],
+        program.programId
+    );
+    await program.methods.initializeVault(backendAuthority.publicKey, baseMint)
+        .accounts({ vault: vaultPda, owner: vaultOwner.publicKey, systemProgram: anchor.web3.SystemProgram.programId })
+        .signers([vaultOwner]).rpc();
+
+    vaultTokenAccount = getAssociatedTokenAddressSync(baseMint, vaultPda, true);
+    await program.methods.initVaultAta().accounts({
+        vault: vaultPda, owner: vaultOwner.publicKey, mint: baseMint,
+        vaultTokenAccount: vaultTokenAccount, tokenProgram: TOKEN_PROGRAM_ID,
+        associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID, systemProgram: anchor.web3.SystemProgram.programId
+    }).signers([vaultOwner]).rpc();
+
+    const ownerAta = await getOrCreateAssociatedTokenAccount(provider.connection, payer, baseMint, vaultOwner.publicKey);
+    await mintTo(provider.connection, payer, baseMint, ownerAta.address, payer.publicKey, 5_000_000);
+    await program.methods.depositToken(new anchor.BN(5_000_000)).accounts({
+        vault: vaultPda, owner: vaultOwner.publicKey, ownerTokenAccount: ownerAta.address,
+        vaultTokenAccount: vaultTokenAccount, tokenProgram: TOKEN_PROGRAM_ID
+    }).signers([vaultOwner]).rpc();
+  });
+
+  const createTraderState = async (trader: anchor.web3.PublicKey, allocationId = 0) => {
+    // Allocation 0 uses no extra seed so it keeps the legacy address
+    const allocationSeed = allocationId === 0
+        ? Buffer.alloc(0)
+        : Buffer.from(new Uint16Array([allocationId]).buffer);
+    const [traderStatePda] = anchor.web3.PublicKey.findProgramAddressSync(
+        [Buffer.from("trader_state"), vaultOwner.publicKey.toBuffer(), trader.toBuffer(), allocationSeed],
+        program.programId
+    );
+    const traderTokenAccount = getAssociatedTokenAddressSync(baseMint, traderStatePda, true);
+    await program.methods.createTraderState(FUNDING_AMOUNT, allocationId).accounts({
+        owner: vaultOwner.publicKey, trader: trader, vault: vaultPda,
+        traderState: traderStatePda, vaultTokenAccount: vaultTokenAccount,
+        traderTokenAccount: traderTokenAccount, mint: baseMint,
+        systemProgram: anchor.web3.SystemProgram.programId,
+        tokenProgram: TOKEN_PROGRAM_ID, associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID
+    }).signers([vaultOwner]).rpc();
+    return { traderStatePda, traderTokenAccount };
+  };
+
+  type SwapSide = { ata: anchor.web3.PublicKey, position?: anchor.web3.PublicKey, pool: anchor.web3.PublicKey };
+
+  // Whitelist `mint` and open the TraderState's ATA and position for it.
+  // The payer's account for the mint is the mock_swap pool (and platform fee account).
+  const openPosition = async (traderStatePda: anchor.web3.PublicKey, mint: anchor.web3.PublicKey): Promise<SwapSide> => {
+    await program.methods.addAllowedMint(mint)
+        .accounts({ owner: vaultOwner.publicKey, vault: vaultPda })
+        .signers([vaultOwner]).rpc();
+    const ata = getAssociatedTokenAddressSync(mint, traderStatePda, true);
+    const [position] = anchor.web3.PublicKey.findProgramAddressSync(
+        [Buffer.from("trader_position"), traderStatePda.toBuffer(), mint.toBuffer()],
+        program.programId
+    );
+    await program.methods.createTraderAta().accounts({
+        owner: vaultOwner.publicKey, vault: vaultPda, traderState: traderStatePda, mint,
+        traderTokenAccount: ata, traderPosition: position,
+        tokenProgram: TOKEN_PROGRAM_ID, associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+        systemProgram: anchor.web3.SystemProgram.programId
+    }).signers([vaultOwner]).rpc();
+    const pool = await getOrCreateAssociatedTokenAccount(provider.connection, payer, mint, payer.publicKey);
+    await mintTo(provider.connection, payer, mint, pool.address, payer.publicKey, 1_000);
+    return { ata, position, pool: pool.address };
+  };
+
+  // Swap through mock_swap: amount_in less the platform fee goes into the input
+  // pool and `amountOut` comes out of the output pool. The payer must be the vault authority.
+  const mockSwapPair = async (
+    traderStatePda: anchor.web3.PublicKey,
+    amountIn: number,
+    input: SwapSide,
+    output: SwapSide,
+    amountOut: number
+  ) => {
+    const [globalConfigPda] = anchor.web3.PublicKey.findProgramAddressSync([Buffer.from("global_config")], program.programId);
+    const config = await program.account.globalConfig.fetch(globalConfigPda);
+    const fee = Math.floor(amountIn * config.platformFeeBps / 10_000);
+    const ix = await mockSwap.methods.swapPair(new anchor.BN(amountIn - fee), new anchor.BN(amountOut))
+        .accounts({
+            authority: traderStatePda,
+            input: input.ata,
+            output: output.ata,
+            poolAuthority: payer.publicKey,
+            poolInput: input.pool,
+            poolOutput: output.pool,
+            tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .instruction();
+    return program.methods.executeTraderSwap(new anchor.BN(amountIn), new anchor.BN(amountOut), new anchor.BN(amountOut), ix.data)
+        .accounts({
+            authority: payer.publicKey,
+            vault: vaultPda,
+            traderState: traderStatePda,
+            inputTokenAccount: input.ata,
+            inputPosition: input.position ?? null,
+            outputTokenAccount: output.ata,
+            outputPosition: output.position ?? null,
+            platformFeeAccount: input.pool,
+            globalConfig: globalConfigPda,
+            jupiterProgram: mockSwap.programId,
+            tokenProgram: TOKEN_PROGRAM_ID,
+            instructions: anchor.web3.SYSVAR_INSTRUCTIONS_PUBKEY,
+        })
+        .remainingAccounts(ix.keys)
+        .signers([payer]).rpc();
+  };
+
+  it("Owner rotates the backend authority", async () => {
+    const rotatedAuthority = anchor.web3.Keypair.generate();
+    const MEMO_PROGRAM_ID = new anchor.web3.PublicKey("MemoSq4gqABAXKb96qnH8TysNcWxMyWCqXgDLGmfcQb");
+    const [globalConfigPda] = anchor.web3.PublicKey.findProgramAddressSync([Buffer.from("global_config")], program.programId);
+    await program.methods.addSwapProgram(MEMO_PROGRAM_ID).accounts({
+        globalConfig: globalConfigPda, admin: payer.publicKey
+    }).signers([payer]).rpc();
+
+    const { traderStatePda, traderTokenAccount } = await createTraderState(anchor.web3.Keypair.generate().publicKey);
+    await program.methods.markTraderInitialized()
+        .accounts({ signer: vaultOwner.publicKey, vault: vaultPda, traderState: traderStatePda })
+        .signers([vaultOwner]).rpc();
+    const platformFeeAta = await getOrCreateAssociatedTokenAccount(provider.connection, payer, baseMint, payer.publicKey);
+    const altMint = await createMint(provider.connection, payer, payer.publicKey, null, 6);
+    const alt = await openPosition(traderStatePda, altMint);
+    // Base -> Alt through the Memo venue, which fills nothing
+    const swap = (authority: anchor.web3.Keypair) =>
+        program.methods.executeTraderSwap(new anchor.BN(10_000), new anchor.BN(0), new anchor.BN(0), Buffer.from("MEMO"))
+            .accounts({
+                authority: authority.publicKey,
+                vault: vaultPda,
+                traderState: traderStatePda,
+                inputTokenAccount: traderTokenAccount,
+                outputTokenAccount: alt.ata,
+                outputPosition: alt.position,
+                platformFeeAccount: platformFeeAta.address,
+                globalConfig: globalConfigPda,
+                jupiterProgram: MEMO_PROGRAM_ID,
+                tokenProgram: TOKEN_PROGRAM_ID,
+                instructions: anchor.web3.SYSVAR_INSTRUCTIONS_PUBKEY,
+            })
+            .signers([authority]);
+
+    await program.methods.setVaultAuthority(rotatedAuthority.publicKey)
+        .accounts({ owner: vaultOwner.publicKey, vault: vaultPda })
+        .signers([vaultOwner]).rpc();
+
+    const vault = await program.account.userVault.fetch(vaultPda);
+    assert.ok(vault.authority.equals(rotatedAuthority.publicKey));
+
+    // New authority can trade
+    await swap(rotatedAuthority).rpc();
+
+    // Old authority is locked out
+    try {
+        await swap(backendAuthority).rpc();
+        assert.fail("Old authority should be rejected after rotation");
+    } catch (e) {
+        assert.include(e.message, "Unauthorized");
+    }
+
+    // Leave the whitelist empty for the capacity test
+    await program.methods.removeAllowedMint(altMint)
+        .accounts({ owner: vaultOwner.publicKey, vault: vaultPda })
+        .signers([vaultOwner]).rpc();
+  });
+
+  it("Backend authority cannot rotate itself", async () => {
+    try {
+        await program.methods.setVaultAuthority(backendAuthority.publicKey)
+            .accounts({ owner: backendAuthority.publicKey, vault: vaultPda })
+            .signers([backendAuthority]).rpc();
+        assert.fail("Only the owner can rotate the authority");
+    } catch (e) {
+        // Seeds derive from the signer, so a non-owner cannot even address the vault
+        assert.ok(e.message.includes("ConstraintSeeds") || e.message.includes("seeds constraint") || e.message.includes("not authorized"));
+    }
+  });
+
+  it("Whitelist grows past the initial 10-mint capacity", async () => {
+    const mints = Array.from({ length: 12 }, () => anchor.web3.Keypair.generate().publicKey);
+    const sizeBefore = (await provider.connection.getAccountInfo(vaultPda)).data.length;
+
+    for (const mint of mints) {
+        await program.methods.addAllowedMint(mint)
+            .accounts({ owner: vaultOwner.publicKey, vault: vaultPda })
+            .signers([vaultOwner]).rpc();
+    }
+
+    const vault = await program.account.userVault.fetch(vaultPda);
+    assert.equal(vault.allowedMints.length, 12);
+    const sizeAfter = (await provider.connection.getAccountInfo(vaultPda)).data.length;
+    assert.isAbove(sizeAfter, sizeBefore, "Vault account should have been reallocated");
+
+    // Re-adding a listed mint is a no-op and must not grow (or charge rent for) the account
+    await program.methods.addAllowedMint(mints[0])
+        .accounts({ owner: vaultOwner.publicKey, vault: vaultPda })
+        .signers([vaultOwner]).rpc();
+    assert.equal((await program.account.userVault.fetch(vaultPda)).allowedMints.length, 12);
+    assert.equal((await provider.connection.getAccountInfo(vaultPda)).data.length, sizeAfter);
+  });
+
+  it("Vault pause cascades to TraderState operations", async () => {
+    const { traderStatePda } = await createTraderState(anchor.web3.Keypair.generate().publicKey);
+
+    await program.methods.pauseVault()
+        .accounts({ owner: vaultOwner.publicKey, vault: vaultPda })
+        .signers([vaultOwner]).rpc();
+    // Idempotent: pausing twice keeps it paused (no toggle race)
+    await program.methods.pauseVault()
+        .accounts({ owner: vaultOwner.publicKey, vault: vaultPda })
+        .signers([vaultOwner]).rpc();
+    assert.isTrue((await program.account.userVault.fetch(vaultPda)).isPaused);
+
+    try {
+        await createTraderState(anchor.web3.Keypair.generate().publicKey);
+        assert.fail("create_trader_state should fail while vault is paused");
+    } catch (e) {
+        assert.include(e.message, "The vault is currently paused");
+    }
+
+    try {
+        await program.methods.markTraderInitialized()
+            .accounts({ signer: vaultOwner.publicKey, vault: vaultPda, traderState: traderStatePda })
+            .signers([vaultOwner]).rpc();
+        assert.fail("mark_trader_initialized should fail while vault is paused");
+    } catch (e) {
+        assert.include(e.message, "The vault is currently paused");
+    }
+
+    await program.methods.unpauseVault()
+        .accounts({ owner: vaultOwner.publicKey, vault: vaultPda })
+        .signers([vaultOwner]).rpc();
+    assert.isFalse((await program.account.userVault.fetch(vaultPda)).isPaused);
+  });
+
+  it("Tops up a TraderState without charging performance fees", async () => {
+    const { traderStatePda, traderTokenAccount } = await createTraderState(anchor.web3.Keypair.generate().publicKey);
+    const TOP_UP = new anchor.BN(250_000);
+
+    await program.methods.fundTraderState(TOP_UP).accounts({
+        owner: vaultOwner.publicKey, vault: vaultPda, traderState: traderStatePda,
+        vaultTokenAccount: vaultTokenAccount, traderTokenAccount: traderTokenAccount,
+        tokenProgram: TOKEN_PROGRAM_ID
+    }).signers([vaultOwner]).rpc();
+
+    const account = await program.account.traderState.fetch(traderStatePda);
+    const expected = FUNDING_AMOUNT.add(TOP_UP).toString();
+    assert.equal(account.currentValue.toString(), expected);
+    assert.equal(account.highWaterMark.toString(), expected, "HWM moves with added capital");
+    assert.equal((await getAccount(provider.connection, traderTokenAccount)).amount.toString(), expected);
+  });
+
+  it("Partially withdraws while keeping the allocation alive", async () => {
+    const { traderStatePda, traderTokenAccount } = await createTraderState(anchor.web3.Keypair.generate().publicKey);
+    const ownerAta = getAssociatedTokenAddressSync(baseMint, vaultOwner.publicKey);
+    const adminFeeAta = await getOrCreateAssociatedTokenAccount(provider.connection, payer, baseMint, payer.publicKey);
+    const WITHDRAW = new anchor.BN(400_000);
+
+    const ownerBefore = (await getAccount(provider.connection, ownerAta)).amount;
+
+    await program.methods.partialWithdrawTraderState(WITHDRAW).accounts({
+        owner: vaultOwner.publicKey, vault: vaultPda, traderState: traderStatePda,
+        traderTokenAccount: traderTokenAccount, vaultTokenAccount: vaultTokenAccount,
+        ownerTokenAccount: ownerAta, performanceFeeAccount: adminFeeAta.address,
+        tokenProgram: TOKEN_PROGRAM_ID
+    }).signers([vaultOwner]).rpc();
+
+    const ownerAfter = (await getAccount(provider.connection, ownerAta)).amount;
+    assert.equal((ownerAfter - ownerBefore).toString(), WITHDRAW.toString());
+
+    const account = await program.account.traderState.fetch(traderStatePda);
+    const remaining = FUNDING_AMOUNT.sub(WITHDRAW).toString();
+    assert.equal(account.currentValue.toString(), remaining);
+    assert.equal(account.highWaterMark.toString(), remaining, "HWM shrinks proportionally");
+    assert.notDeepEqual(account.status, { paused: {} }, "Allocation keeps running");
+  });
+
+  it("Charges the performance fee on the withdrawn share of gains above the HWM", async () => {
+    const [globalConfigPda] = anchor.web3.PublicKey.findProgramAddressSync([Buffer.from("global_config")], program.programId);
+    const { traderStatePda, traderTokenAccount } = await createTraderState(anchor.web3.Keypair.generate().publicKey);
+    await program.methods.markTraderInitialized()
+        .accounts({ signer: vaultOwner.publicKey, vault: vaultPda, traderState: traderStatePda })
+        .signers([vaultOwner]).rpc();
+
+    // Buy 1_000 alt through mock_swap, then sell it back above cost to realize a profit
+    await program.methods.addSwapProgram(mockSwap.programId).accounts({
+        globalConfig: globalConfigPda, admin: payer.publicKey
+    }).signers([payer]).rpc();
+    await program.methods.setVaultAuthority(payer.publicKey)
+        .accounts({ owner: vaultOwner.publicKey, vault: vaultPda })
+        .signers([vaultOwner]).rpc();
+
+    const PROFIT = 500_000;
+    const adminFeeAta = await getOrCreateAssociatedTokenAccount(provider.connection, payer, baseMint, payer.publicKey);
+    await mintTo(provider.connection, payer, baseMint, adminFeeAta.address, payer.publicKey, PROFIT);
+    const base = { ata: traderTokenAccount, pool: adminFeeAta.address };
+    const alt = await openPosition(traderStatePda, await createMint(provider.connection, payer, payer.publicKey, null, 6));
+
+    await mockSwapPair(traderStatePda, 10_000, base, alt, 1_000);
+    const cost = (await program.account.traderPosition.fetch(alt.position)).costBasis.toNumber();
+    await mockSwapPair(traderStatePda, 1_000, alt, base, cost + PROFIT);
+
+    const before = await program.account.traderState.fetch(traderStatePda);
+    assert.isAbove(before.currentValue.toNumber(), before.highWaterMark.toNumber(), "Swap should realize a gain");
+
+    const config = await program.account.globalConfig.fetch(globalConfigPda);
+    const WITHDRAW = new anchor.BN(400_000);
+    const withdrawnGain = before.currentValue.sub(before.highWaterMark).mul(WITHDRAW).div(before.currentValue);
+    const expectedFee = withdrawnGain.muln(config.performanceFeeBps).divn(10000);
+    assert.isTrue(expectedFee.gtn(0));
+
+    const ownerAta = getAssociatedTokenAddressSync(baseMint, vaultOwner.publicKey);
+    const ownerBefore = (await getAccount(provider.connection, ownerAta)).amount;
+    const feeBefore = (await getAccount(provider.connection, adminFeeAta.address)).amount;
+
+    await program.methods.partialWithdrawTraderState(WITHDRAW).accounts({
+        owner: vaultOwner.publicKey, vault: vaultPda, traderState: traderStatePda,
+        traderTokenAccount: traderTokenAccount, vaultTokenAccount: vaultTokenAccount,
+        ownerTokenAccount: ownerAta, performanceFeeAccount: adminFeeAta.address,
+        tokenProgram: TOKEN_PROGRAM_ID
+    }).signers([vaultOwner]).rpc();
+
+    const feeAfter = (await getAccount(provider.connection, adminFeeAta.address)).amount;
+    const ownerAfter = (await getAccount(provider.connection, ownerAta)).amount;
+    assert.equal((feeAfter - feeBefore).toString(), expectedFee.toString(), "Fee on the withdrawn gain goes to the admin");
+    assert.equal((ownerAfter - ownerBefore).toString(), WITHDRAW.sub(expectedFee).toString(), "Owner receives the rest");
+
+    const after = await program.account.traderState.fetch(traderStatePda);
+    assert.equal(after.currentValue.toString(), before.currentValue.sub(WITHDRAW).toString());
+    assert.equal(after.cumulativeProfit.toString(), before.cumulativeProfit.sub(expectedFee).toString());
+    const hwmReduction = before.highWaterMark.mul(WITHDRAW).div(before.currentValue);
+    assert.equal(after.highWaterMark.toString(), before.highWaterMark.sub(hwmReduction).toString());
+  });
+
+  it("Rejects a partial withdrawal while a position is open", async () => {
+    // Relies on the mock_swap venue and payer authority set up above
+    const { traderStatePda, traderTokenAccount } = await createTraderState(anchor.web3.Keypair.generate().publicKey);
+    await program.methods.markTraderInitialized()
+        .accounts({ signer: vaultOwner.publicKey, vault: vaultPda, traderState: traderStatePda })
+        .signers([vaultOwner]).rpc();
+
+    const adminFeeAta = getAssociatedTokenAddressSync(baseMint, payer.publicKey);
+    const alt = await openPosition(traderStatePda, await createMint(provider.connection, payer, payer.publicKey, null, 6));
+    await mockSwapPair(traderStatePda, 10_000, { ata: traderTokenAccount, pool: adminFeeAta }, alt, 1_000);
+
+    const account = await program.account.traderState.fetch(traderStatePda);
+    assert.equal(account.openPositions, 1);
+
+    try {
+        await program.methods.partialWithdrawTraderState(new anchor.BN(100_000)).accounts({
+            owner: vaultOwner.publicKey, vault: vaultPda, traderState: traderStatePda,
+            traderTokenAccount: traderTokenAccount, vaultTokenAccount: vaultTokenAccount,
+            ownerTokenAccount: getAssociatedTokenAddressSync(baseMint, vaultOwner.publicKey),
+            performanceFeeAccount: adminFeeAta, tokenProgram: TOKEN_PROGRAM_ID
+        }).signers([vaultOwner]).rpc();
+        assert.fail("Partial withdrawal should be rejected while a position is open");
+    } catch (e) {
+        assert.include(e.message, "PositionsOpen");
+    }
+  });
+
+  it("Caps token-to-token swaps at the cost basis they move", async () => {
+    // Relies on the mock_swap venue and payer authority set up above
+    const { traderStatePda, traderTokenAccount } = await createTraderState(anchor.web3.Keypair.generate().publicKey);
+    await program.methods.markTraderInitialized()
+        .accounts({ signer: vaultOwner.publicKey, vault: vaultPda, traderState: traderStatePda })
+        .signers([vaultOwner]).rpc();
+    const alt = await openPosition(traderStatePda, await createMint(provider.connection, payer, payer.publicKey, null, 6));
+    const hop = await openPosition(traderStatePda, await createMint(provider.connection, payer, payer.publicKey, null, 6));
+
+    // Half the allocation buys 1_000 alt
+    const base = { ata: traderTokenAccount, pool: getAssociatedTokenAddressSync(baseMint, payer.publicKey) };
+    await mockSwapPair(traderStatePda, 500_000, base, alt, 1_000);
+    const altCost = (await program.account.traderPosition.fetch(alt.position)).costBasis.toNumber();
+
+    // 10% max trade
+    await program.methods.setRiskLimits(1_000, 0)
+        .accounts({ owner: vaultOwner.publicKey, traderState: traderStatePda })
+        .signers([vaultOwner]).rpc();
+    const account = await program.account.traderState.fetch(traderStatePda);
+    const maxTrade = Math.floor(account.currentValue.toNumber() * 1_000 / 10_000);
+
+    // 400 alt carries 40% of the position's cost, far above the cap
+    assert.isAbove(Math.floor(altCost * 400 / 1_000), maxTrade);
+    try {
+        await mockSwapPair(traderStatePda, 400, alt, hop, 400);
+        assert.fail("Should have failed TradeSizeExceeded");
+    } catch (e) {
+        assert.include(e.message, "Trade size exceeds the TraderState maximum");
+    }
+
+    // 100 alt carries 10% of the cost, within the cap
+    await mockSwapPair(traderStatePda, 100, alt, hop, 100);
+    const hopPosition = await program.account.traderPosition.fetch(hop.position);
+    assert.equal(hopPosition.costBasis.toNumber(), Math.floor(altCost * 100 / 1_000));
+
+    await program.methods.setRiskLimits(0, 0)
+        .accounts({ owner: vaultOwner.publicKey, traderState: traderStatePda })
+        .signers([vaultOwner]).rpc();
+  });
+
+  it("Rejects a partial withdrawal during settlement", async () => {
+    const { traderStatePda, traderTokenAccount } = await createTraderState(anchor.web3.Keypair.generate().publicKey);
+    const adminFeeAta = getAssociatedTokenAddressSync(baseMint, payer.publicKey);
+    await program.methods.beginTraderSettlement()
+        .accounts({ owner: vaultOwner.publicKey, traderState: traderStatePda })
+        .signers([vaultOwner]).rpc();
+
+    try {
+        await program.methods.partialWithdrawTraderState(new anchor.BN(100_000)).accounts({
+            owner: vaultOwner.publicKey, vault: vaultPda, traderState: traderStatePda,
+            traderTokenAccount: traderTokenAccount, vaultTokenAccount: vaultTokenAccount,
+            ownerTokenAccount: getAssociatedTokenAddressSync(baseMint, vaultOwner.publicKey),
+            performanceFeeAccount: adminFeeAta, tokenProgram: TOKEN_PROGRAM_ID
+        }).signers([vaultOwner]).rpc();
+        assert.fail("Partial withdrawal should be rejected while settling");
+    } catch (e) {
+        assert.include(e.message, "TraderSettling");
+    }
+  });
+
+  it("Rejects a partial withdrawal from a settlement that was paused", async () => {
+    const { traderStatePda, traderTokenAccount } = await createTraderState(anchor.web3.Keypair.generate().publicKey);
+    const adminFeeAta = getAssociatedTokenAddressSync(baseMint, payer.publicKey);
+    await program.methods.beginTraderSettlement()
+        .accounts({ owner: vaultOwner.publicKey, traderState: traderStatePda })
+        .signers([vaultOwner]).rpc();
+    await program.methods.pauseTraderState()
+        .accounts({ owner: vaultOwner.publicKey, traderState: traderStatePda })
+        .signers([vaultOwner]).rpc();
+
+    const account = await program.account.traderState.fetch(traderStatePda);
+    assert.deepEqual(account.status, { paused: {} });
+    assert.deepEqual(account.resumeStatus, { settling: {} });
+
+    try {
+        await program.methods.partialWithdrawTraderState(new anchor.BN(100_000)).accounts({
+            owner: vaultOwner.publicKey, vault: vaultPda, traderState: traderStatePda,
+            traderTokenAccount: traderTokenAccount, vaultTokenAccount: vaultTokenAccount,
+            ownerTokenAccount: getAssociatedTokenAddressSync(baseMint, vaultOwner.publicKey),
+            performanceFeeAccount: adminFeeAta, tokenProgram: TOKEN_PROGRAM_ID
+        }).signers([vaultOwner]).rpc();
+        assert.fail("Pausing should not lift the settlement block");
+    } catch (e) {
+        assert.include(e.message, "TraderSettling");
+    }
+  });
+
+  it("Rejects funding a settlement that was paused", async () => {
+    const { traderStatePda, traderTokenAccount } = await createTraderState(anchor.web3.Keypair.generate().publicKey);
+    await program.methods.beginTraderSettlement()
+        .accounts({ owner: vaultOwner.publicKey, traderState: traderStatePda })
+        .signers([vaultOwner]).rpc();
+    await program.methods.pauseTraderState()
+        .accounts({ owner: vaultOwner.publicKey, traderState: traderStatePda })
+        .signers([vaultOwner]).rpc();
+
+    try {
+        await program.methods.fundTraderState(new anchor.BN(100_000)).accounts({
+            owner: vaultOwner.publicKey, vault: vaultPda, traderState: traderStatePda,
+            vaultTokenAccount: vaultTokenAccount, traderTokenAccount: traderTokenAccount,
+            tokenProgram: TOKEN_PROGRAM_ID
+        }).signers([vaultOwner]).rpc();
+        assert.fail("Pausing should not lift the settlement block on funding");
+    } catch (e) {
+        assert.include(e.message, "TraderSettling");
+    }
+
+    const account = await program.account.traderState.fetch(traderStatePda);
+    assert.equal(account.currentValue.toString(), FUNDING_AMOUNT.toString());
+  });
+
+  it("Runs multiple allocations for the same trader side by side", async () => {
+    const trader = anchor.web3.Keypair.generate().publicKey;
+    const first = await createTraderState(trader, 0);
+    const second = await createTraderState(trader, 1);
+
+    assert.ok(!first.traderStatePda.equals(second.traderStatePda));
+    const a = await program.account.traderState.fetch(first.traderStatePda);
+    const b = await program.account.traderState.fetch(second.traderStatePda);
+    assert.equal(a.allocationId, 0);
+    assert.equal(b.allocationId, 1);
+    assert.ok(a.trader.equals(b.trader));
+  });
+
+  it("Rejects migrating a TraderState that is already current", async () => {
+    const { traderStatePda, traderTokenAccount } = await createTraderState(anchor.web3.Keypair.generate().publicKey);
+    try {
+        await program.methods.migrateTraderState()
+            .accounts({ owner: vaultOwner.publicKey, traderState: traderStatePda, traderTokenAccount: traderTokenAccount })
+            .signers([vaultOwner]).rpc();
+        assert.fail("Current-layout accounts should not migrate");
+    } catch (e) {
+        assert.include(e.message, "already uses the current account layout");
+    }
+  });
+});
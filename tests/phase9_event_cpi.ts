@@ -0,0 +1,190 @@
+import * as anchor from "@coral-xyz/anchor";
+import { Program } from "@coral-xyz/anchor";
+import { StellalphaVault } from "../target/types/stellalpha_vault";
+import { assert } from "chai";
+import {
+  createMint,
+  mintTo,
+  getOrCreateAssociatedTokenAccount,
+  getAssociatedTokenAddressSync,
+  TOKEN_PROGRAM_ID,
+  ASSOCIATED_TOKEN_PROGRAM_ID
+} from "@solana/spl-token";
+import * as fs from "fs";
+import * as os from "os";
+import { isEventCpiBuild, cpiEvents, logEvents } from "./utils/events";
+
+// Runs only against a program built with the event-cpi feature:
+//   anchor test -- --features event-cpi
+describe("Phase 9: Event CPI", () => {
+  const provider = anchor.AnchorProvider.env();
+  anchor.setProvider(provider);
+
+  const program = anchor.workspace.StellalphaVault as Program<StellalphaVault>;
+
+  const walletPath = os.homedir() + "/.config/solana/devnet-wallet.json";
+  const rawKey = JSON.parse(fs.readFileSync(walletPath, "utf-8"));
+  const payer = anchor.web3.Keypair.fromSecretKey(Uint8Array.from(rawKey));
+
+  const vaultOwner = anchor.web3.Keypair.generate();
+  const trader = anchor.web3.Keypair.generate();
+  const MEMO_PROGRAM_ID = new anchor.web3.PublicKey("MemoSq4gqABAXKb96qnH8TysNcWxMyWCqXgDLGmfcQb");
+  const FUNDING_AMOUNT = new anchor.BN(1_000_000);
+
+  let baseMint: anchor.web3.PublicKey;
+  let vaultPda: anchor.web3.PublicKey;
+  let globalConfigPda: anchor.web3.PublicKey;
+  let traderStatePda: anchor.web3.PublicKey;
+  let traderTokenAccount: anchor.web3.PublicKey;
+  let altAta: anchor.web3.PublicKey;
+  let altPosition: anchor.web3.PublicKey;
+  let adminFeeAccount: anchor.web3.PublicKey;
+
+  const fetchTx = (sig: string) =>
+    provider.connection.getTransaction(sig, { commitment: "confirmed", maxSupportedTransactionVersion: 0 });
+
+  before(async function () {
+    if (!isEventCpiBuild(program)) {
+        this.skip();
+    }
+
+    const transferTx = new anchor.web3.Transaction().add(
+        anchor.web3.SystemProgram.transfer({
+            fromPubkey: payer.publicKey,
+            toPubkey: vaultOwner.publicKey,
+            lamports: 100_000_000
+        })
+    );
+    await anchor.web3.sendAndConfirmTransaction(provider.connection, transferTx, [payer]);
+
+    baseMint = await createMint(provider.connection, payer, payer.publicKey, null, 6);
+
+    [globalConfigPda] = anchor.web3.PublicKey.findProgramAddressSync([Buffer.from("global_config")], program.programId);
+    try {
+        await program.methods.initializeGlobalConfig(payer.publicKey).accounts({
+            globalConfig: globalConfigPda, admin: payer.publicKey, systemProgram: anchor.web3.SystemProgram.programId
+        }).signers([payer]).rpc();
+    } catch (e) {}
+    await program.methods.addSwapProgram(MEMO_PROGRAM_ID).accounts({
+        globalConfig: globalConfigPda, admin: payer.publicKey
+    }).signers([payer]).rpc();
+
+    [vaultPda] = anchor.web3.PublicKey.findProgramAddressSync(
+        [Buffer.from("user_vault_v1"), vaultOwner.publicKey.toBuffer()],
+        program.programId
+    );
+    await program.methods.initializeVault(vaultOwner.publicKey, baseMint)
+        .accounts({ vault: vaultPda, owner: vaultOwner.publicKey, systemProgram: anchor.web3.SystemProgram.programId })
+        .signers([vaultOwner]).rpc();
+
+    const vaultTokenAccount = getAssociatedTokenAddressSync(baseMint, vaultPda, true);
+    await program.methods.initVaultAta().accounts({
+        vault: vaultPda, owner: vaultOwner.publicKey, mint: baseMint,
+        vaultTokenAccount: vaultTokenAccount, tokenProgram: TOKEN_PROGRAM_ID,
+        associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID, systemProgram: anchor.web3.SystemProgram.programId
+    }).signers([vaultOwner]).rpc();
+
+    const ownerAta = await getOrCreateAssociatedTokenAccount(provider.connection, payer, baseMint, vaultOwner.publicKey);
+    await mintTo(provider.connection, payer, baseMint, ownerAta.address, payer.publicKey, 5_000_000);
+    await program.methods.depositToken(new anchor.BN(5_000_000)).accounts({
+        vault: vaultPda, owner: vaultOwner.publicKey, ownerTokenAccount: ownerAta.address,
+        vaultTokenAccount: vaultTokenAccount, tokenProgram: TOKEN_PROGRAM_ID
+    }).signers([vaultOwner]).rpc();
+
+    [traderStatePda] = anchor.web3.PublicKey.findProgramAddressSync(
+        [Buffer.from("trader_state"), vaultOwner.publicKey.toBuffer(), trader.publicKey.toBuffer()],
+        program.programId
+    );
+    traderTokenAccount = getAssociatedTokenAddressSync(baseMint, traderStatePda, true);
+    await program.methods.createTraderState(FUNDING_AMOUNT, 0).accounts({
+        owner: vaultOwner.publicKey, trader: trader.publicKey, vault: vaultPda,
+        traderState: traderStatePda, vaultTokenAccount: vaultTokenAccount,
+        traderTokenAccount: traderTokenAccount, mint: baseMint,
+        systemProgram: anchor.web3.SystemProgram.programId,
+        tokenProgram: TOKEN_PROGRAM_ID, associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID
+    }).signers([vaultOwner]).rpc();
+    await program.methods.markTraderInitialized()
+        .accounts({ signer: vaultOwner.publicKey, vault: vaultPda, traderState: traderStatePda })
+        .signers([vaultOwner]).rpc();
+
+    // The swap goes Base -> Alt; the Memo venue fills nothing
+    const altMint = await createMint(provider.connection, payer, payer.publicKey, null, 6);
+    await program.methods.addAllowedMint(altMint)
+        .accounts({ owner: vaultOwner.publicKey, vault: vaultPda })
+        .signers([vaultOwner]).rpc();
+    altAta = getAssociatedTokenAddressSync(altMint, traderStatePda, true);
+    [altPosition] = anchor.web3.PublicKey.findProgramAddressSync(
+        [Buffer.from("trader_position"), traderStatePda.toBuffer(), altMint.toBuffer()],
+        program.programId
+    );
+    await program.methods.createTraderAta().accounts({
+        owner: vaultOwner.publicKey, vault: vaultPda, traderState: traderStatePda, mint: altMint,
+        traderTokenAccount: altAta, traderPosition: altPosition,
+        tokenProgram: TOKEN_PROGRAM_ID, associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+        systemProgram: anchor.web3.SystemProgram.programId
+    }).signers([vaultOwner]).rpc();
+
+    adminFeeAccount = (await getOrCreateAssociatedTokenAccount(provider.connection, payer, baseMint, payer.publicKey)).address;
+  });
+
+  it("Delivers TraderSwapExecuted as a self-CPI inner instruction", async () => {
+    const AMOUNT_IN = new anchor.BN(10_000);
+    const sig = await program.methods.executeTraderSwap(AMOUNT_IN, new anchor.BN(0), new anchor.BN(0), Buffer.from("MEMO"))
+        .accounts({
+            authority: vaultOwner.publicKey,
+            vault: vaultPda,
+            traderState: traderStatePda,
+            inputTokenAccount: traderTokenAccount,
+            outputTokenAccount: altAta,
+            outputPosition: altPosition,
+            platformFeeAccount: adminFeeAccount,
+            globalConfig: globalConfigPda,
+            jupiterProgram: MEMO_PROGRAM_ID,
+            tokenProgram: TOKEN_PROGRAM_ID,
+            instructions: anchor.web3.SYSVAR_INSTRUCTIONS_PUBKEY,
+        })
+        .signers([vaultOwner])
+        .rpc({ commitment: "confirmed" });
+    const tx = await fetchTx(sig);
+
+    const swapEvent = cpiEvents(program, tx).find((e) => e.name === "traderSwapExecuted");
+    assert.ok(swapEvent, "TraderSwapExecuted should arrive as an inner instruction");
+    assert.ok(swapEvent.data.traderState.equals(traderStatePda));
+    assert.equal(swapEvent.data.amountIn.toString(), AMOUNT_IN.toString());
+    assert.isFalse(swapEvent.data.isSync);
+
+    const account = await program.account.traderState.fetch(traderStatePda);
+    assert.equal(swapEvent.data.currentValue.toString(), account.currentValue.toString());
+    assert.isUndefined(
+        logEvents(program, tx).find((e) => e.name === "traderSwapExecuted"),
+        "Event should not also be logged"
+    );
+  });
+
+  it("Delivers TraderStateSettled as a self-CPI inner instruction", async () => {
+    await program.methods.pauseTraderState()
+        .accounts({ owner: vaultOwner.publicKey, traderState: traderStatePda })
+        .signers([vaultOwner]).rpc();
+
+    const sig = await program.methods.settleTraderState().accounts({
+        owner: vaultOwner.publicKey,
+        vault: vaultPda,
+        traderState: traderStatePda,
+        traderTokenAccount: traderTokenAccount,
+        performanceFeeAccount: adminFeeAccount
+    }).signers([vaultOwner]).rpc({ commitment: "confirmed" });
+    const tx = await fetchTx(sig);
+
+    const settledEvent = cpiEvents(program, tx).find((e) => e.name === "traderStateSettled");
+    assert.ok(settledEvent, "TraderStateSettled should arrive as an inner instruction");
+    assert.ok(settledEvent.data.traderState.equals(traderStatePda));
+
+    const account = await program.account.traderState.fetch(traderStatePda);
+    assert.deepEqual(account.status, { settled: {} });
+    assert.equal(settledEvent.data.highWaterMark.toString(), account.highWaterMark.toString());
+    assert.isUndefined(
+        logEvents(program, tx).find((e) => e.name === "traderStateSettled"),
+        "Event should not also be logged"
+    );
+  });
+});
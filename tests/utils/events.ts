@@ -0,0 +1,32 @@
+import * as anchor from "@coral-xyz/anchor";
+
+// anchor_lang::event::EVENT_IX_TAG_LE: prefix of every emit_cpi! self-invocation
+const EVENT_IX_TAG_LE = Buffer.from("e445a52e51cb9a1d", "hex");
+
+// Built with `--features event-cpi`: swap and settlement take the event_authority account
+export const isEventCpiBuild = (program: anchor.Program<any>): boolean =>
+  program.idl.instructions
+    .find((ix) => ix.name === "executeTraderSwap")
+    .accounts.some((account) => account.name === "eventAuthority");
+
+// Events emitted with emit! (program logs)
+export const logEvents = (program: anchor.Program<any>, tx: anchor.web3.VersionedTransactionResponse) => {
+  const parser = new anchor.EventParser(program.programId, new anchor.BorshCoder(program.idl));
+  return [...parser.parseLogs(tx.meta.logMessages)];
+};
+
+// Events emitted with emit_cpi! (inner instructions of the program invoking itself)
+export const cpiEvents = (program: anchor.Program<any>, tx: anchor.web3.VersionedTransactionResponse) => {
+  const keys = tx.transaction.message.staticAccountKeys;
+  return (tx.meta.innerInstructions ?? [])
+    .flatMap((inner) => inner.instructions)
+    .filter((ix) => keys[ix.programIdIndex].equals(program.programId))
+    .map((ix) => Buffer.from(anchor.utils.bytes.bs58.decode(ix.data)))
+    .filter((data) => data.subarray(0, 8).equals(EVENT_IX_TAG_LE))
+    .map((data) => program.coder.events.decode(anchor.utils.bytes.base64.encode(data.subarray(8))))
+    .filter((event) => event !== null);
+};
+
+// Events of a transaction, from whichever channel this build emits on
+export const txEvents = (program: anchor.Program<any>, tx: anchor.web3.VersionedTransactionResponse) =>
+  isEventCpiBuild(program) ? cpiEvents(program, tx) : logEvents(program, tx);